@@ -2,7 +2,13 @@ pub const MAX_VERTEX_ATTRIBUTES: u32 = 16;
 pub const MAX_VERTEX_BINDINGS: u32 = 16;
 pub const MAX_VERTEX_ATTRIBUTE_OFFSET: u32 = 2047;
 pub const MAX_VERTEX_BINDING_STRIDE: u32 = 2048;
+pub const MAX_VERTEX_OUTPUT_COMPONENTS: u32 = 64;
+pub const MAX_FRAGMENT_INPUT_COMPONENTS: u32 = 64;
+/// Number of 4-component `Location`s passed from vertex to fragment shader.
+pub const MAX_VARYING_LOCATIONS: u32 = MAX_VERTEX_OUTPUT_COMPONENTS / 4;
 pub const MAX_VIEWPORTS: u32 = 15;
+pub const MAX_COLOR_ATTACHMENTS: u32 = 8;
+pub const MAX_SAMPLE_COUNT: u32 = 8;
 pub const MAX_VIEWPORT_DIMENSIONS: (u32, u32) = (16384, 16384);
 pub const VIEWPORT_BOUNDS_RANGE: (f32, f32) = (
     -2.0 * MAX_VIEWPORT_DIMENSIONS.0 as f32,
@@ -10,3 +16,6 @@ pub const VIEWPORT_BOUNDS_RANGE: (f32, f32) = (
 );
 pub const MAX_CLIP_DISTANCES: u32 = 4;
 pub const MAX_CULL_DISTANCES: u32 = 4;
+pub const LINE_WIDTH_RANGE: (f32, f32) = (1.0, 64.0);
+pub const LINE_WIDTH_GRANULARITY: f32 = 1.0;
+pub const MAX_DRAW_INDIRECT_COUNT: u32 = u32::MAX;
@@ -88,6 +88,146 @@ pub enum FrontFace {
     Clockwise,
 }
 
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum CompareOp {
+    #[default]
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always,
+}
+
+impl CompareOp {
+    /// Compares `reference` value (e.g. fragment depth) against `test` value (e.g. stored depth).
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VkCompareOp
+    pub fn compare<T: PartialOrd>(&self, reference: T, test: T) -> bool {
+        match *self {
+            Self::Never => false,
+            Self::Less => reference < test,
+            Self::Equal => reference == test,
+            Self::LessOrEqual => reference <= test,
+            Self::Greater => reference > test,
+            Self::NotEqual => reference != test,
+            Self::GreaterOrEqual => reference >= test,
+            Self::Always => true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum StencilOp {
+    #[default]
+    Keep,
+    Zero,
+    Replace,
+    IncrementAndClamp,
+    DecrementAndClamp,
+    Invert,
+    IncrementAndWrap,
+    DecrementAndWrap,
+}
+
+impl StencilOp {
+    /// Returns new stencil value computed from `stored` stencil value and `reference`.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#VkStencilOp
+    pub const fn apply(&self, stored: u8, reference: u8) -> u8 {
+        match *self {
+            Self::Keep => stored,
+            Self::Zero => 0,
+            Self::Replace => reference,
+            Self::IncrementAndClamp => stored.saturating_add(1),
+            Self::DecrementAndClamp => stored.saturating_sub(1),
+            Self::Invert => !stored,
+            Self::IncrementAndWrap => stored.wrapping_add(1),
+            Self::DecrementAndWrap => stored.wrapping_sub(1),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum LogicOp {
+    Clear,
+    And,
+    AndReverse,
+    #[default]
+    Copy,
+    AndInverted,
+    NoOp,
+    Xor,
+    Or,
+    Nor,
+    Equivalent,
+    Invert,
+    OrReverse,
+    CopyInverted,
+    OrInverted,
+    Nand,
+    Set,
+}
+
+impl LogicOp {
+    /// Returns result of logical operation between source `s` and destination `d` bits.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#framebuffer-logicop
+    pub const fn apply(&self, s: u8, d: u8) -> u8 {
+        match *self {
+            Self::Clear => 0,
+            Self::And => s & d,
+            Self::AndReverse => s & !d,
+            Self::Copy => s,
+            Self::AndInverted => !s & d,
+            Self::NoOp => d,
+            Self::Xor => s ^ d,
+            Self::Or => s | d,
+            Self::Nor => !(s | d),
+            Self::Equivalent => !(s ^ d),
+            Self::Invert => !d,
+            Self::OrReverse => s | !d,
+            Self::CopyInverted => !s,
+            Self::OrInverted => !s | d,
+            Self::Nand => !(s & d),
+            Self::Set => !0,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BlendFactor {
+    #[default]
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha,
+    SrcAlphaSaturate,
+    Src1Color,
+    OneMinusSrc1Color,
+    Src1Alpha,
+    OneMinusSrc1Alpha,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum BlendOp {
+    #[default]
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max,
+}
+
 #[derive(Debug, Clone)]
 pub struct VertexBuffer {
     pub binding_number: VertexBindingNumber,
@@ -102,6 +242,13 @@ pub struct IndexBuffer {
     pub index_size: u8,
 }
 
+impl IndexBuffer {
+    /// Index value that restarts strips and fans, i.e. maximum value of index type.
+    pub const fn primitive_restart_index(&self) -> u32 {
+        ((1u64 << (self.index_size as u64 * 8)) - 1) as u32
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct DescriptorBuffer {
     pub binding: MemoryBinding,
@@ -6,9 +6,20 @@ pub enum Format {
     R8Unorm,
     R8G8Unorm,
     R8G8B8A8Unorm,
+    R8G8B8A8Snorm,
+    R8G8B8A8Uint,
+    R8G8B8A8Sint,
+    R32Sfloat,
+    R32G32Sfloat,
+    R32G32B32Sfloat,
     R32G32B32A32Sfloat,
     A2b10g10r10UnormPack32,
     D16Unorm,
+    X8D24UnormPack32,
+    D32Sfloat,
+    S8Uint,
+    D24UnormS8Uint,
+    D32SfloatS8Uint,
 }
 
 pub struct FormatInfo {
@@ -19,10 +30,14 @@ pub struct FormatInfo {
     pub bytes2: Option<Range<usize>>,
     pub bytes3: Option<Range<usize>>,
     pub is_unorm: bool,
+    pub is_snorm: bool,
+    /// Components are signed, signed integers are sign extended when widened.
+    pub is_signed: bool,
+    /// Components are unsigned or signed integers stored as raw bits.
+    pub is_integer: bool,
 }
 
 impl Format {
-    #[allow(unreachable_code)]
     pub const fn info(&self) -> FormatInfo {
         match *self {
             Self::R8Unorm => FormatInfo {
@@ -33,6 +48,9 @@ impl Format {
                 bytes2: None,
                 bytes3: None,
                 is_unorm: true,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: false,
             },
             Self::R8G8Unorm => FormatInfo {
                 bytes_per_pixel: 2,
@@ -42,6 +60,9 @@ impl Format {
                 bytes2: None,
                 bytes3: None,
                 is_unorm: true,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: false,
             },
             Self::R8G8B8A8Unorm => FormatInfo {
                 bytes_per_pixel: 4,
@@ -51,6 +72,81 @@ impl Format {
                 bytes2: Some(2..3),
                 bytes3: Some(3..4),
                 is_unorm: true,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: false,
+            },
+            Self::R8G8B8A8Snorm => FormatInfo {
+                bytes_per_pixel: 4,
+                bytes_per_component: Some(1),
+                bytes0: Some(0..1),
+                bytes1: Some(1..2),
+                bytes2: Some(2..3),
+                bytes3: Some(3..4),
+                is_unorm: false,
+                is_snorm: true,
+                is_signed: true,
+                is_integer: false,
+            },
+            Self::R8G8B8A8Uint => FormatInfo {
+                bytes_per_pixel: 4,
+                bytes_per_component: Some(1),
+                bytes0: Some(0..1),
+                bytes1: Some(1..2),
+                bytes2: Some(2..3),
+                bytes3: Some(3..4),
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: true,
+            },
+            Self::R8G8B8A8Sint => FormatInfo {
+                bytes_per_pixel: 4,
+                bytes_per_component: Some(1),
+                bytes0: Some(0..1),
+                bytes1: Some(1..2),
+                bytes2: Some(2..3),
+                bytes3: Some(3..4),
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: true,
+                is_integer: true,
+            },
+            Self::R32Sfloat => FormatInfo {
+                bytes_per_pixel: 4,
+                bytes_per_component: Some(4),
+                bytes0: Some(0..4),
+                bytes1: None,
+                bytes2: None,
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: true,
+                is_integer: false,
+            },
+            Self::R32G32Sfloat => FormatInfo {
+                bytes_per_pixel: 8,
+                bytes_per_component: Some(4),
+                bytes0: Some(0..4),
+                bytes1: Some(4..8),
+                bytes2: None,
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: true,
+                is_integer: false,
+            },
+            Self::R32G32B32Sfloat => FormatInfo {
+                bytes_per_pixel: 12,
+                bytes_per_component: Some(4),
+                bytes0: Some(0..4),
+                bytes1: Some(4..8),
+                bytes2: Some(8..12),
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: true,
+                is_integer: false,
             },
             Self::R32G32B32A32Sfloat => FormatInfo {
                 bytes_per_pixel: 16,
@@ -60,15 +156,22 @@ impl Format {
                 bytes2: Some(8..12),
                 bytes3: Some(12..16),
                 is_unorm: false,
+                is_snorm: false,
+                is_signed: true,
+                is_integer: false,
             },
+            // Components are not byte aligned, see `packed_components`.
             Self::A2b10g10r10UnormPack32 => FormatInfo {
                 bytes_per_pixel: 4,
                 bytes_per_component: None,
-                bytes0: todo!(),
-                bytes1: todo!(),
-                bytes2: todo!(),
-                bytes3: todo!(),
-                is_unorm: false,
+                bytes0: None,
+                bytes1: None,
+                bytes2: None,
+                bytes3: None,
+                is_unorm: true,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: false,
             },
             Self::D16Unorm => FormatInfo {
                 bytes_per_pixel: 2,
@@ -78,7 +181,152 @@ impl Format {
                 bytes2: None,
                 bytes3: None,
                 is_unorm: false,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: false,
+            },
+            Self::X8D24UnormPack32 => FormatInfo {
+                bytes_per_pixel: 4,
+                bytes_per_component: None,
+                bytes0: Some(0..3),
+                bytes1: None,
+                bytes2: None,
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: false,
+            },
+            Self::D32Sfloat => FormatInfo {
+                bytes_per_pixel: 4,
+                bytes_per_component: Some(4),
+                bytes0: Some(0..4),
+                bytes1: None,
+                bytes2: None,
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: true,
+                is_integer: false,
             },
+            Self::S8Uint => FormatInfo {
+                bytes_per_pixel: 1,
+                bytes_per_component: Some(1),
+                bytes0: None,
+                bytes1: Some(0..1),
+                bytes2: None,
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: true,
+            },
+            Self::D24UnormS8Uint => FormatInfo {
+                bytes_per_pixel: 4,
+                bytes_per_component: None,
+                bytes0: Some(0..3),
+                bytes1: Some(3..4),
+                bytes2: None,
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: false,
+                is_integer: false,
+            },
+            // Stencil is stored after depth, remaining bytes are padding.
+            Self::D32SfloatS8Uint => FormatInfo {
+                bytes_per_pixel: 8,
+                bytes_per_component: None,
+                bytes0: Some(0..4),
+                bytes1: Some(4..5),
+                bytes2: None,
+                bytes3: None,
+                is_unorm: false,
+                is_snorm: false,
+                is_signed: true,
+                is_integer: false,
+            },
+        }
+    }
+
+    /// Bit ranges of components within 32-bit texel word of packed format.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#formats-packed
+    pub const fn packed_components(&self) -> Option<[Range<u32>; 4]> {
+        match *self {
+            Self::A2b10g10r10UnormPack32 => Some([0..10, 10..20, 20..30, 30..32]),
+            _ => None,
+        }
+    }
+
+    pub const fn has_depth(&self) -> bool {
+        matches!(
+            *self,
+            Self::D16Unorm
+                | Self::X8D24UnormPack32
+                | Self::D32Sfloat
+                | Self::D24UnormS8Uint
+                | Self::D32SfloatS8Uint
+        )
+    }
+
+    pub const fn has_stencil(&self) -> bool {
+        matches!(
+            *self,
+            Self::S8Uint | Self::D24UnormS8Uint | Self::D32SfloatS8Uint
+        )
+    }
+
+    /// Reads depth value from depth/stencil texel `bytes`.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fundamentals-fixedfpconv
+    pub fn depth_from_bytes(&self, bytes: &[u8]) -> f32 {
+        match *self {
+            Self::D16Unorm => {
+                let value = u16::from_ne_bytes([bytes[0], bytes[1]]);
+                value as f32 / 65535.0f32
+            }
+            Self::X8D24UnormPack32 | Self::D24UnormS8Uint => {
+                let value = u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+                (value & 0x00ff_ffff) as f32 / 16777215.0f32
+            }
+            Self::D32Sfloat | Self::D32SfloatS8Uint => {
+                f32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+            }
+            _ => unreachable!("{:?} has no depth component", self),
+        }
+    }
+
+    /// Writes `depth` into depth/stencil texel `bytes`, stencil is preserved.
+    pub fn depth_to_bytes(&self, depth: f32, bytes: &mut [u8]) {
+        let depth = Vector4::from_sfloat32_raw(depth, 0.0, 0.0, 0.0);
+        match *self {
+            Self::D16Unorm => bytes[0..2].copy_from_slice(&depth.to_unorm16_bytes(0)),
+            Self::X8D24UnormPack32 | Self::D24UnormS8Uint => {
+                bytes[0..3].copy_from_slice(&depth.to_unorm24_bytes(0)[0..3]);
+            }
+            Self::D32Sfloat | Self::D32SfloatS8Uint => {
+                bytes[0..4].copy_from_slice(&depth.to_sfloat32_bytes(0));
+            }
+            _ => unreachable!("{:?} has no depth component", self),
+        }
+    }
+
+    /// Reads stencil value from depth/stencil texel `bytes`.
+    pub fn stencil_from_bytes(&self, bytes: &[u8]) -> u8 {
+        match *self {
+            Self::S8Uint => bytes[0],
+            Self::D24UnormS8Uint => bytes[3],
+            Self::D32SfloatS8Uint => bytes[4],
+            _ => unreachable!("{:?} has no stencil component", self),
+        }
+    }
+
+    /// Writes `stencil` into depth/stencil texel `bytes`, depth is preserved.
+    pub fn stencil_to_bytes(&self, stencil: u8, bytes: &mut [u8]) {
+        match *self {
+            Self::S8Uint => bytes[0] = stencil,
+            Self::D24UnormS8Uint => bytes[3] = stencil,
+            Self::D32SfloatS8Uint => bytes[4] = stencil,
+            _ => unreachable!("{:?} has no stencil component", self),
         }
     }
 }
@@ -193,6 +441,17 @@ impl Vector4 {
         value.to_ne_bytes()
     }
 
+    fn to_unorm24_bytes(self, index: impl std::slice::SliceIndex<[u64], Output = u64>) -> [u8; 4] {
+        let value = f32::from_bits(self.components[index] as u32);
+        let value = (value.clamp(0.0, 1.0) * 16777215.0f32).round() as u32;
+        value.to_ne_bytes()
+    }
+
+    fn to_snorm8_byte(self, index: impl std::slice::SliceIndex<[u64], Output = u64>) -> u8 {
+        let value = f32::from_bits(self.components[index] as u32).clamp(-1.0, 1.0);
+        (value * 127.0f32).round() as i8 as u8
+    }
+
     #[allow(dead_code)]
     fn to_unorm32_bytes(self, index: impl std::slice::SliceIndex<[u64], Output = u64>) -> [u8; 4] {
         let value = f32::from_bits(self.components[index] as u32);
@@ -216,6 +475,32 @@ impl Vector4 {
                 result[2] = self.to_unorm8_byte(2);
                 result[3] = self.to_unorm8_byte(3);
             }
+            Format::R8G8B8A8Snorm => {
+                result[0] = self.to_snorm8_byte(0);
+                result[1] = self.to_snorm8_byte(1);
+                result[2] = self.to_snorm8_byte(2);
+                result[3] = self.to_snorm8_byte(3);
+            }
+            // NOTE: Integer components are stored as raw bits, signed values are truncated from
+            // their two's complement representation.
+            Format::R8G8B8A8Uint | Format::R8G8B8A8Sint => {
+                result[0] = self.get_as_uint8(0);
+                result[1] = self.get_as_uint8(1);
+                result[2] = self.get_as_uint8(2);
+                result[3] = self.get_as_uint8(3);
+            }
+            Format::R32Sfloat => {
+                result[0..4].copy_from_slice(&self.to_sfloat32_bytes(0));
+            }
+            Format::R32G32Sfloat => {
+                result[0..4].copy_from_slice(&self.to_sfloat32_bytes(0));
+                result[4..8].copy_from_slice(&self.to_sfloat32_bytes(1));
+            }
+            Format::R32G32B32Sfloat => {
+                result[0..4].copy_from_slice(&self.to_sfloat32_bytes(0));
+                result[4..8].copy_from_slice(&self.to_sfloat32_bytes(1));
+                result[8..12].copy_from_slice(&self.to_sfloat32_bytes(2));
+            }
             Format::R32G32B32A32Sfloat => {
                 result[0..4].copy_from_slice(&self.to_sfloat32_bytes(0));
                 result[4..8].copy_from_slice(&self.to_sfloat32_bytes(1));
@@ -223,16 +508,47 @@ impl Vector4 {
                 result[12..16].copy_from_slice(&self.to_sfloat32_bytes(3));
             }
             Format::A2b10g10r10UnormPack32 => {
-                unimplemented!()
+                let components = format.packed_components().unwrap_or_else(|| unreachable!());
+                let mut texel = 0u32;
+                for (index, bits) in components.into_iter().enumerate() {
+                    let max = (1u32 << bits.len()) - 1;
+                    let value = self.get_as_sfloat32(index).clamp(0.0, 1.0);
+                    texel |= ((value * max as f32).round() as u32) << bits.start;
+                }
+                result.copy_from_slice(&texel.to_ne_bytes());
             }
             Format::D16Unorm => {
                 result[0..2].copy_from_slice(&self.to_unorm16_bytes(0));
             }
+            Format::X8D24UnormPack32 => {
+                result[0..4].copy_from_slice(&self.to_unorm24_bytes(0));
+            }
+            Format::D32Sfloat => {
+                result[0..4].copy_from_slice(&self.to_sfloat32_bytes(0));
+            }
+            Format::S8Uint => {
+                result[0] = self.get_as_uint8(1);
+            }
+            Format::D24UnormS8Uint | Format::D32SfloatS8Uint => {
+                format.depth_to_bytes(self.get_as_sfloat32(0), &mut result);
+                format.stencil_to_bytes(self.get_as_uint8(1), &mut result);
+            }
         }
         result
     }
 
     pub fn from_vertex_buffer_bytes(format: Format, bytes: &[u8]) -> Self {
+        if let Some(components) = format.packed_components() {
+            let mut raw = [0u8; 4];
+            let len = raw.len().min(bytes.len());
+            raw[..len].copy_from_slice(&bytes[..len]);
+            let texel = u32::from_ne_bytes(raw);
+            let [r, g, b, a] = components.map(|bits| {
+                let max = (1u32 << bits.len()) - 1;
+                ((texel >> bits.start) & max) as f32 / max as f32
+            });
+            return Self::from_sfloat32_raw(r, g, b, a);
+        }
         let (s0, s1, s2, s3) = (
             format.info().bytes0,
             format.info().bytes1,
@@ -282,15 +598,43 @@ impl Vector4 {
                 s3.map_or_else(|| 0, f),
             ],
         };
-        if format.info().is_unorm {
+        let mut result = if format.info().is_unorm {
             match format.info().bytes_per_component {
                 Some(1) => result.to_unorm8(),
                 Some(4) => result.to_unorm32(),
                 _ => unimplemented!(),
             }
+        } else if format.info().is_snorm {
+            match format.info().bytes_per_component {
+                Some(1) => result.to_snorm8(),
+                // `Format` has no SNORM variants with wider components.
+                _ => unreachable!("{format:?}"),
+            }
+        } else if format.info().is_integer && format.info().is_signed {
+            // NOTE: Signed integers are sign extended to 32-bit components.
+            let Some(bytes_per_component) = format.info().bytes_per_component else {
+                // `Format` has no signed integer variants with components of different sizes.
+                unreachable!("{format:?}")
+            };
+            let shift = 64 - 8 * u32::from(bytes_per_component);
+            Self {
+                components: result
+                    .components
+                    .map(|c| ((c << shift) as i64 >> shift) as i32 as u32 as u64),
+            }
         } else {
             result
+        };
+        // NOTE: Missing G and B components are 0, missing A component is 1.
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fxvertex-input-extraction
+        if format.info().bytes3.is_none() {
+            result.components[3] = if format.info().is_integer {
+                1
+            } else {
+                f32::to_bits(1.0) as u64
+            };
         }
+        result
     }
 
     pub fn to_unorm8(self) -> Self {
@@ -302,6 +646,13 @@ impl Vector4 {
         )
     }
 
+    pub fn to_snorm8(self) -> Self {
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fundamentals-fixedfpconv
+        let snorm8 = |c: u64| (c as u8 as i8 as f32 / 127.0f32).max(-1.0);
+        let [r, g, b, a] = self.components.map(snorm8);
+        Self::from_sfloat32_raw(r, g, b, a)
+    }
+
     pub fn to_unorm32(self) -> Self {
         Self::from_sfloat32_raw(
             self.get_as_unorm32(0),
@@ -332,15 +683,34 @@ pub struct Vertex {
     pub position: Position,
     pub point_size: f32,
     pub index: u32,
+    pub instance_index: u32,
     pub clip_distances: [f32; crate::consts::MAX_CLIP_DISTANCES as usize],
+    /// Vertex input attributes indexed by `Location`.
+    pub attributes: [Vector4; crate::consts::MAX_VERTEX_ATTRIBUTES as usize],
 }
 
 #[derive(Debug, Copy, Clone)]
 pub struct Fragment {
     pub position: Position,
-    pub color: Color,
+    /// Interpolated vertex shader outputs, indexed by `Location`.
+    pub varyings: [Vector4; crate::consts::MAX_VARYING_LOCATIONS as usize],
+    pub front_facing: bool,
+    /// Bit mask of covered samples.
+    pub sample_mask: u32,
+    /// Depth at each sample location.
+    pub sample_depths: [f32; crate::consts::MAX_SAMPLE_COUNT as usize],
 }
 
+impl Fragment {
+    /// Helper invocation covers no samples, it only provides values for derivatives within quad.
+    pub const fn is_helper_invocation(&self) -> bool {
+        self.sample_mask == 0
+    }
+}
+
+/// Fragments of 2x2 pixel quad, ordered (x, y), (x + 1, y), (x, y + 1), (x + 1, y + 1).
+pub type FragmentQuad = [Fragment; 4];
+
 #[derive(Debug, Copy, Clone, Default)]
 pub struct Offset3<T> {
     pub x: T,
@@ -0,0 +1,208 @@
+use common::graphics::{BlendFactor, BlendOp, LogicOp};
+use common::math::Format;
+
+use crate::ColorBlendAttachmentState;
+
+/// Blends fragment color `src` with color attachment value `dst` and applies color write mask.
+/// For fixed-point attachments `clamp` limits inputs to [0, 1] or [-1, 1] for signed ones.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#framebuffer-blending
+pub fn blend(
+    state: &ColorBlendAttachmentState,
+    blend_constants: [f32; 4],
+    src: [f32; 4],
+    dst: [f32; 4],
+    clamp: Option<(f32, f32)>,
+) -> [f32; 4] {
+    let clamp = |color: [f32; 4]| {
+        if let Some((min, max)) = clamp {
+            color.map(|c| c.clamp(min, max))
+        } else {
+            color
+        }
+    };
+    let (src, dst, constants) = (clamp(src), clamp(dst), clamp(blend_constants));
+
+    let blended = if state.blend_enable {
+        let src_factor = blend_factor(state.src_color_blend_factor, src, dst, constants);
+        let dst_factor = blend_factor(state.dst_color_blend_factor, src, dst, constants);
+        let src_alpha_factor = blend_factor(state.src_alpha_blend_factor, src, dst, constants);
+        let dst_alpha_factor = blend_factor(state.dst_alpha_blend_factor, src, dst, constants);
+        let mut blended = [0.0f32; 4];
+        for i in 0..3 {
+            blended[i] = blend_op(
+                state.color_blend_op,
+                src[i],
+                dst[i],
+                src_factor[i],
+                dst_factor[i],
+            );
+        }
+        blended[3] = blend_op(
+            state.alpha_blend_op,
+            src[3],
+            dst[3],
+            src_alpha_factor[3],
+            dst_alpha_factor[3],
+        );
+        blended
+    } else {
+        src
+    };
+
+    let mut result = dst;
+    for i in 0..4 {
+        if state.color_write_mask[i] {
+            result[i] = blended[i];
+        }
+    }
+    result
+}
+
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#framebuffer-blendfactors
+fn blend_factor(
+    factor: BlendFactor,
+    src: [f32; 4],
+    dst: [f32; 4],
+    constants: [f32; 4],
+) -> [f32; 4] {
+    let [_, _, _, src_alpha] = src;
+    let [_, _, _, dst_alpha] = dst;
+    let [_, _, _, constant_alpha] = constants;
+    match factor {
+        BlendFactor::Zero => [0.0; 4],
+        BlendFactor::One => [1.0; 4],
+        BlendFactor::SrcColor => src,
+        BlendFactor::OneMinusSrcColor => src.map(|c| 1.0 - c),
+        BlendFactor::DstColor => dst,
+        BlendFactor::OneMinusDstColor => dst.map(|c| 1.0 - c),
+        BlendFactor::SrcAlpha => [src_alpha; 4],
+        BlendFactor::OneMinusSrcAlpha => [1.0 - src_alpha; 4],
+        BlendFactor::DstAlpha => [dst_alpha; 4],
+        BlendFactor::OneMinusDstAlpha => [1.0 - dst_alpha; 4],
+        BlendFactor::ConstantColor => constants,
+        BlendFactor::OneMinusConstantColor => constants.map(|c| 1.0 - c),
+        BlendFactor::ConstantAlpha => [constant_alpha; 4],
+        BlendFactor::OneMinusConstantAlpha => [1.0 - constant_alpha; 4],
+        BlendFactor::SrcAlphaSaturate => {
+            let f = src_alpha.min(1.0 - dst_alpha);
+            [f, f, f, 1.0]
+        }
+        BlendFactor::Src1Color
+        | BlendFactor::OneMinusSrc1Color
+        | BlendFactor::Src1Alpha
+        | BlendFactor::OneMinusSrc1Alpha => unreachable!("dualSrcBlend is not supported"),
+    }
+}
+
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#framebuffer-blendoperations
+fn blend_op(op: BlendOp, src: f32, dst: f32, src_factor: f32, dst_factor: f32) -> f32 {
+    match op {
+        BlendOp::Add => src.mul_add(src_factor, dst * dst_factor),
+        BlendOp::Subtract => src.mul_add(src_factor, -dst * dst_factor),
+        BlendOp::ReverseSubtract => dst.mul_add(dst_factor, -src * src_factor),
+        BlendOp::Min => src.min(dst),
+        BlendOp::Max => src.max(dst),
+    }
+}
+
+/// Applies logical operation to fragment color `src` and color attachment value `dst`, both
+/// encoded in attachment `format`, and applies color write mask.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#framebuffer-logicop
+pub fn logic_op(
+    op: LogicOp,
+    color_write_mask: [bool; 4],
+    format: Format,
+    src: &[u8],
+    dst: &[u8],
+) -> Vec<u8> {
+    src.iter()
+        .zip(dst)
+        .zip(write_mask_bits(format, color_write_mask))
+        .map(|((&s, &d), mask)| (op.apply(s, d) & mask) | (d & !mask))
+        .collect()
+}
+
+/// Returns bits of texel in `format` written by components enabled in `color_write_mask`.
+/// Components of packed formats are not byte aligned, so their bit fields are masked.
+fn write_mask_bits(format: Format, color_write_mask: [bool; 4]) -> Vec<u8> {
+    if let Some(components) = format.packed_components() {
+        let mut mask = 0u32;
+        for (bits, write) in components.into_iter().zip(color_write_mask) {
+            if write {
+                mask |= (((1u64 << bits.len()) - 1) << bits.start) as u32;
+            }
+        }
+        return mask.to_ne_bytes().to_vec();
+    }
+
+    let info = format.info();
+    let mut mask = vec![0u8; info.bytes_per_pixel as usize];
+    for (bytes, write) in [info.bytes0, info.bytes1, info.bytes2, info.bytes3]
+        .into_iter()
+        .zip(color_write_mask)
+    {
+        if let (Some(bytes), true) = (bytes, write) {
+            mask[bytes].fill(0xff);
+        }
+    }
+    mask
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::math::Color;
+
+    #[test]
+    fn alpha_blending_with_write_mask() {
+        let state = ColorBlendAttachmentState {
+            blend_enable: true,
+            src_color_blend_factor: BlendFactor::SrcAlpha,
+            dst_color_blend_factor: BlendFactor::OneMinusSrcAlpha,
+            color_blend_op: BlendOp::Add,
+            src_alpha_blend_factor: BlendFactor::One,
+            dst_alpha_blend_factor: BlendFactor::Zero,
+            alpha_blend_op: BlendOp::Add,
+            color_write_mask: [true, true, false, true],
+        };
+        let result = blend(
+            &state,
+            [0.0; 4],
+            [1.0, 0.0, 0.0, 0.25],
+            [0.0, 1.0, 0.5, 1.0],
+            Some((0.0, 1.0)),
+        );
+        assert_eq!(result, [0.25, 0.75, 0.5, 0.25]);
+    }
+
+    #[test]
+    fn xor_logic_op_with_write_mask() {
+        let result = logic_op(
+            LogicOp::Xor,
+            [true, true, true, false],
+            Format::R8G8B8A8Unorm,
+            &[0xff, 0x0f, 0x00, 0xff],
+            &[0x0f, 0x0f, 0xaa, 0x11],
+        );
+        assert_eq!(result, [0xf0, 0x00, 0xaa, 0x11]);
+    }
+
+    #[test]
+    fn logic_op_on_packed_bit_fields() {
+        let format = Format::A2b10g10r10UnormPack32;
+        let texel = |r: u32, g: u32, b: u32, a: u32| {
+            (r | g << 10 | b << 20 | a << 30).to_ne_bytes().to_vec()
+        };
+        let result = logic_op(
+            LogicOp::Xor,
+            [true, false, true, true],
+            format,
+            &texel(0x3ff, 0x3ff, 0x001, 0b11),
+            &texel(0x00f, 0x155, 0x201, 0b01),
+        );
+        assert_eq!(result, texel(0x3f0, 0x155, 0x200, 0b10));
+
+        let color = Color::from_vertex_buffer_bytes(format, &result);
+        assert_eq!(color.to_bytes(format), result);
+    }
+}
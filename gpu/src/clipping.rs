@@ -0,0 +1,213 @@
+use crate::rasterization::Interpolations;
+use shader::glsl::VertexShaderOutput;
+
+/// Guard band extent in normalized device coordinates.
+/// Primitives are clipped against x and y planes only if they extend past the guard band,
+/// otherwise rasterization is limited to the viewport.
+const GUARD_BAND: f32 = 256.0;
+
+/// Minimum clip-space w of clipped vertices, avoids division by zero in perspective division.
+const W_EPSILON: f32 = 1.0e-6;
+
+/// Clip plane given as signed distance of clip-space position (x, y, z, w), positive inside.
+type ClipPlane = fn([f32; 4]) -> f32;
+
+/// Near and far planes, not used when depth clamp is enabled.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vertexpostproc-clipping
+const DEPTH_PLANES: [ClipPlane; 2] = [|[_, _, z, _]| z, |[_, _, z, w]| w - z];
+
+/// Planes that require true clipping, in addition to depth planes.
+const CLIP_PLANES: [ClipPlane; 5] = [
+    |[_, _, _, w]| w - W_EPSILON,
+    |[x, _, _, w]| GUARD_BAND.mul_add(w, x),
+    |[x, _, _, w]| GUARD_BAND.mul_add(w, -x),
+    |[_, y, _, w]| GUARD_BAND.mul_add(w, y),
+    |[_, y, _, w]| GUARD_BAND.mul_add(w, -y),
+];
+
+/// View volume planes, in addition to depth planes.
+/// Primitives outside any of them are trivially rejected.
+const VIEW_VOLUME_PLANES: [ClipPlane; 4] = [
+    |[x, _, _, w]| w + x,
+    |[x, _, _, w]| w - x,
+    |[_, y, _, w]| w + y,
+    |[_, y, _, w]| w - y,
+];
+
+fn with_depth_planes<const N: usize>(
+    planes: [ClipPlane; N],
+    depth_clamp: bool,
+) -> impl Iterator<Item = ClipPlane> {
+    let depth_planes = if depth_clamp {
+        &[][..]
+    } else {
+        &DEPTH_PLANES[..]
+    };
+    depth_planes.iter().copied().chain(planes)
+}
+
+fn distance(plane: ClipPlane, vertex: &VertexShaderOutput) -> f32 {
+    plane(vertex.position.get_as_f32_array())
+}
+
+fn is_outside_plane(plane: ClipPlane, vertices: &[VertexShaderOutput]) -> bool {
+    vertices.iter().all(|vertex| distance(plane, vertex) < 0.0)
+}
+
+fn is_trivially_rejected(vertices: &[VertexShaderOutput], depth_clamp: bool) -> bool {
+    with_depth_planes(VIEW_VOLUME_PLANES, depth_clamp)
+        .any(|plane| is_outside_plane(plane, vertices))
+}
+
+pub fn is_point_inside_view_volume(vertex: &VertexShaderOutput, depth_clamp: bool) -> bool {
+    !is_trivially_rejected(&[*vertex], depth_clamp)
+}
+
+/// Clips line, with `depth_clamp` near and far planes are ignored.
+pub fn clip_line(
+    vertices: [VertexShaderOutput; 2],
+    depth_clamp: bool,
+    interpolations: &Interpolations,
+) -> Option<[VertexShaderOutput; 2]> {
+    if is_trivially_rejected(&vertices, depth_clamp) {
+        return None;
+    }
+
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for plane in with_depth_planes(CLIP_PLANES, depth_clamp) {
+        let d0 = distance(plane, &vertices[0]);
+        let d1 = distance(plane, &vertices[1]);
+        match (d0 < 0.0, d1 < 0.0) {
+            (true, true) => return None,
+            (true, false) => t0 = t0.max(d0 / (d0 - d1)),
+            (false, true) => t1 = t1.min(d0 / (d0 - d1)),
+            (false, false) => {}
+        }
+        if t0 > t1 {
+            return None;
+        }
+    }
+    Some([
+        vertices[0].lerp(&vertices[1], t0, &interpolations.modes),
+        vertices[0].lerp(&vertices[1], t1, &interpolations.modes),
+    ])
+}
+
+/// Clips triangle and triangulates resulting polygon as fan, preserving winding order.
+/// With `depth_clamp` near and far planes are ignored.
+pub fn clip_triangle(
+    vertices: [VertexShaderOutput; 3],
+    depth_clamp: bool,
+    interpolations: &Interpolations,
+) -> Vec<[VertexShaderOutput; 3]> {
+    if is_trivially_rejected(&vertices, depth_clamp) {
+        return vec![];
+    }
+
+    // Sutherland-Hodgman polygon clipping.
+    let mut polygon = vertices.to_vec();
+    for plane in with_depth_planes(CLIP_PLANES, depth_clamp) {
+        if polygon.iter().all(|vertex| distance(plane, vertex) >= 0.0) {
+            continue;
+        }
+        let mut clipped = Vec::with_capacity(polygon.len() + 1);
+        for (i, current) in polygon.iter().enumerate() {
+            let next = &polygon[(i + 1) % polygon.len()];
+            let d_current = distance(plane, current);
+            let d_next = distance(plane, next);
+            if d_current >= 0.0 {
+                clipped.push(*current);
+            }
+            if (d_current >= 0.0) != (d_next >= 0.0) {
+                clipped.push(current.lerp(
+                    next,
+                    d_current / (d_current - d_next),
+                    &interpolations.modes,
+                ));
+            }
+        }
+        polygon = clipped;
+        if polygon.len() < 3 {
+            return vec![];
+        }
+    }
+
+    (1..polygon.len() - 1)
+        .map(|i| [polygon[0], polygon[i], polygon[i + 1]])
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::math::{Position, Vector4};
+    use shader::glsl::Interpolation;
+
+    fn vertex(x: f32, y: f32, z: f32, w: f32) -> VertexShaderOutput {
+        VertexShaderOutput {
+            position: Position::from_sfloat32_raw(x, y, z, w),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn triangle_crossing_near_plane() {
+        let triangles = clip_triangle(
+            [
+                vertex(0.0, 0.0, 0.5, 1.0),
+                vertex(1.0, 0.0, -1.0, 1.0),
+                vertex(0.0, 1.0, -1.0, 1.0),
+            ],
+            false,
+            &Interpolations::default(),
+        );
+        assert_eq!(triangles.len(), 1);
+        for vertex in triangles.iter().flatten() {
+            assert!(vertex.position.get_as_sfloat32(2) >= -f32::EPSILON);
+        }
+    }
+
+    #[test]
+    fn triangle_inside_guard_band_is_not_clipped() {
+        let triangle = [
+            vertex(-4.0, -4.0, 0.5, 1.0),
+            vertex(4.0, -4.0, 0.5, 1.0),
+            vertex(0.0, 4.0, 0.5, 1.0),
+        ];
+        assert_eq!(
+            clip_triangle(triangle, false, &Interpolations::default()),
+            vec![triangle]
+        );
+    }
+
+    #[test]
+    fn depth_clamp_disables_near_plane_clipping() {
+        let triangle = [
+            vertex(0.0, 0.0, 0.5, 1.0),
+            vertex(1.0, 0.0, -1.0, 1.0),
+            vertex(0.0, 1.0, -1.0, 1.0),
+        ];
+        assert_eq!(
+            clip_triangle(triangle, true, &Interpolations::default()),
+            vec![triangle]
+        );
+    }
+
+    #[test]
+    fn no_perspective_varyings_of_clipped_vertices_are_interpolated_in_screen_space() {
+        let mut interpolations = Interpolations::default();
+        interpolations.modes[1] = Interpolation::NoPerspective;
+        let mut line = [vertex(-1.0, 0.0, 0.5, 1.0), vertex(3.0, 0.0, -1.0, 3.0)];
+        line[1].varyings[0] = Vector4::from_sfloat32_raw(1.0, 1.0, 1.0, 1.0);
+        line[1].varyings[1] = Vector4::from_sfloat32_raw(1.0, 1.0, 1.0, 1.0);
+        let [_, clipped] =
+            clip_line(line, false, &interpolations).expect("line crosses near plane");
+
+        // Near plane cuts line at a third of its clip-space length, where screen-space x is 0.2,
+        // which is 60% of the way from -1 to 1.
+        let [x, _, _, w] = clipped.position.get_as_f32_array();
+        assert!((x / w - 0.2).abs() < 1.0e-6);
+        assert!((clipped.varyings[0].get_as_sfloat32(0) - 1.0 / 3.0).abs() < 1.0e-6);
+        assert!((clipped.varyings[1].get_as_sfloat32(0) - 0.6).abs() < 1.0e-6);
+    }
+}
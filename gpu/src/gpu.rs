@@ -1,10 +1,12 @@
 use crate::{
-    GraphicsPipeline, InputAssemblyState, Memory, RasterizationState, RenderArea, RenderTarget,
-    RenderTargetIndex, ViewportState,
+    texel_offset, ColorBlendState, DepthStencilState, DepthStencilTarget, DynamicStates,
+    GraphicsPipeline, InputAssemblyState, Memory, MultisampleState, RasterizationState, RenderArea,
+    RenderTarget, RenderTargetIndex, Scissor, StencilFaces, TessellationState, Viewport,
+    ViewportState,
 };
 use common::{
     graphics::{DescriptorBuffer, DescriptorImage, IndexBuffer, VertexBuffer, VertexInputState},
-    math::{Color, Extent3, Format, Offset3},
+    math::{Color, Extent2, Extent3, Format, Offset3, Range2},
 };
 use log::warn;
 use shader::glsl::ShaderState;
@@ -49,10 +51,20 @@ impl Gpu {
                 } => {
                     self.copy_buffer_to_buffer(src_buffer, dst_buffer, region);
                 }
+                Command::ResolveImage {
+                    src_image,
+                    dst_image,
+                    region,
+                } => {
+                    self.resolve_image(src_image, dst_image, region);
+                }
                 Command::ExecuteCommands { command_buffer } => {
                     warn!("TODO: Avoid submit recursion.");
                     self.submit(command_buffer);
                 }
+                Command::SetFramebufferExtent { extent } => {
+                    self.graphics_pipeline.set_framebuffer_extent(extent);
+                }
                 Command::BindRenderTarget { render_target } => {
                     self.graphics_pipeline.bind_render_target(render_target);
                 }
@@ -71,6 +83,61 @@ impl Gpu {
                         color,
                     );
                 }
+                Command::BindDepthStencilTarget {
+                    depth_stencil_target,
+                } => {
+                    self.graphics_pipeline
+                        .bind_depth_stencil_target(depth_stencil_target);
+                }
+                Command::UnbindDepthStencilTarget => {
+                    self.graphics_pipeline.unbind_depth_stencil_target();
+                }
+                Command::ClearDepthStencilTarget {
+                    render_area,
+                    depth,
+                    stencil,
+                } => {
+                    self.graphics_pipeline.clear_depth_stencil_target(
+                        &mut self.memory,
+                        render_area,
+                        depth,
+                        stencil,
+                    );
+                }
+                Command::ClearColorImage {
+                    image,
+                    format,
+                    samples,
+                    render_area,
+                    color,
+                } => {
+                    GraphicsPipeline::clear_color_image(
+                        &mut self.memory,
+                        &image,
+                        format,
+                        samples,
+                        render_area,
+                        color,
+                    );
+                }
+                Command::ClearDepthStencilImage {
+                    image,
+                    format,
+                    samples,
+                    render_area,
+                    depth,
+                    stencil,
+                } => {
+                    GraphicsPipeline::clear_depth_stencil_image(
+                        &mut self.memory,
+                        &image,
+                        format,
+                        samples,
+                        render_area,
+                        depth,
+                        stencil,
+                    );
+                }
 
                 Command::SetShaderState { shader_state } => {
                     self.graphics_pipeline.set_shader_state(shader_state);
@@ -85,6 +152,10 @@ impl Gpu {
                     self.graphics_pipeline
                         .set_input_assembly_state(input_assembly_state);
                 }
+                Command::SetTessellationState { tessellation_state } => {
+                    self.graphics_pipeline
+                        .set_tessellation_state(tessellation_state);
+                }
                 Command::SetViewportState { viewport_state } => {
                     self.graphics_pipeline.set_viewport_state(viewport_state);
                 }
@@ -94,6 +165,68 @@ impl Gpu {
                     self.graphics_pipeline
                         .set_rasterization_state(rasterization_state);
                 }
+                Command::SetMultisampleState { multisample_state } => {
+                    self.graphics_pipeline
+                        .set_multisample_state(multisample_state);
+                }
+                Command::SetDepthStencilState {
+                    depth_stencil_state,
+                } => {
+                    self.graphics_pipeline
+                        .set_depth_stencil_state(depth_stencil_state);
+                }
+                Command::SetColorBlendState { color_blend_state } => {
+                    self.graphics_pipeline
+                        .set_color_blend_state(color_blend_state);
+                }
+                Command::SetDepthBias {
+                    constant_factor,
+                    clamp,
+                    slope_factor,
+                } => {
+                    self.graphics_pipeline
+                        .set_depth_bias(constant_factor, clamp, slope_factor);
+                }
+                Command::SetBlendConstants { blend_constants } => {
+                    self.graphics_pipeline.set_blend_constants(blend_constants);
+                }
+                Command::SetDynamicStates { dynamic_states } => {
+                    self.graphics_pipeline.set_dynamic_states(dynamic_states);
+                }
+                Command::SetViewports {
+                    first_viewport,
+                    viewports,
+                } => {
+                    self.graphics_pipeline
+                        .set_viewports(first_viewport, viewports);
+                }
+                Command::SetScissors {
+                    first_scissor,
+                    scissors,
+                } => {
+                    self.graphics_pipeline.set_scissors(first_scissor, scissors);
+                }
+                Command::SetLineWidth { line_width } => {
+                    self.graphics_pipeline.set_line_width(line_width);
+                }
+                Command::SetDepthBounds { depth_bounds } => {
+                    self.graphics_pipeline.set_depth_bounds(depth_bounds);
+                }
+                Command::SetStencilCompareMask {
+                    faces,
+                    compare_mask,
+                } => {
+                    self.graphics_pipeline
+                        .set_stencil_compare_mask(faces, compare_mask);
+                }
+                Command::SetStencilWriteMask { faces, write_mask } => {
+                    self.graphics_pipeline
+                        .set_stencil_write_mask(faces, write_mask);
+                }
+                Command::SetStencilReference { faces, reference } => {
+                    self.graphics_pipeline
+                        .set_stencil_reference(faces, reference);
+                }
                 Command::BindVertexBuffer { vertex_buffer } => {
                     self.graphics_pipeline.bind_vertex_buffer(vertex_buffer);
                 }
@@ -114,6 +247,34 @@ impl Gpu {
                         first_instance,
                     );
                 }
+                Command::DrawPrimitiveIndirect {
+                    buffer,
+                    offset,
+                    draw_count,
+                    stride,
+                } => {
+                    self.graphics_pipeline.draw_primitive_indirect(
+                        &mut self.memory,
+                        buffer,
+                        offset,
+                        draw_count,
+                        stride,
+                    );
+                }
+                Command::DrawPrimitiveIndexedIndirect {
+                    buffer,
+                    offset,
+                    draw_count,
+                    stride,
+                } => {
+                    self.graphics_pipeline.draw_primitive_indexed_indirect(
+                        &mut self.memory,
+                        buffer,
+                        offset,
+                        draw_count,
+                        stride,
+                    );
+                }
                 Command::DrawPrimitiveIndexed {
                     index_count,
                     instance_count,
@@ -213,6 +374,71 @@ impl Gpu {
         );
     }
 
+    /// Resolves multisampled `src_image` into single-sampled `dst_image`. Samples of
+    /// floating-point and normalized formats are averaged, integer formats take sample 0.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#copies-resolve
+    fn resolve_image(
+        &mut self,
+        src_image: DescriptorImage,
+        dst_image: DescriptorImage,
+        region: RegionResolveImage,
+    ) {
+        assert!(region.src_offset.x >= 0 && region.src_offset.y >= 0);
+        assert!(region.dst_offset.x >= 0 && region.dst_offset.y >= 0);
+        assert_eq!(region.src_offset.z, 0);
+        assert_eq!(region.dst_offset.z, 0);
+        assert_eq!(region.extent.depth, 1);
+        let format = region.format;
+        let samples = region.samples;
+        let bytes_per_pixel = format.info().bytes_per_pixel as u64;
+        for y in 0..region.extent.height as u64 {
+            for x in 0..region.extent.width as u64 {
+                let src_offset = |sample| {
+                    texel_offset(
+                        &src_image,
+                        format,
+                        samples,
+                        region.src_offset.x as u64 + x,
+                        region.src_offset.y as u64 + y,
+                        sample,
+                    )
+                };
+                let bytes = if format.info().is_integer {
+                    self.memory
+                        .read_bytes(&src_image.binding, src_offset(0), bytes_per_pixel)
+                        .to_vec()
+                } else {
+                    let mut sum = [0.0f32; 4];
+                    for sample in 0..samples {
+                        let color = Color::from_vertex_buffer_bytes(
+                            format,
+                            self.memory.read_bytes(
+                                &src_image.binding,
+                                src_offset(sample),
+                                bytes_per_pixel,
+                            ),
+                        );
+                        for (sum, value) in sum.iter_mut().zip(color.get_as_f32_array()) {
+                            *sum += value;
+                        }
+                    }
+                    let [r, g, b, a] = sum.map(|value| value / samples as f32);
+                    Color::from_sfloat32_raw(r, g, b, a).to_bytes(format)
+                };
+                let dst_offset = texel_offset(
+                    &dst_image,
+                    format,
+                    1,
+                    region.dst_offset.x as u64 + x,
+                    region.dst_offset.y as u64 + y,
+                    0,
+                );
+                self.memory
+                    .write_bytes(&bytes, &dst_image.binding, dst_offset);
+            }
+        }
+    }
+
     fn copy_buffer_to_buffer(
         &mut self,
         src_buffer: DescriptorBuffer,
@@ -267,9 +493,17 @@ pub enum Command {
         dst_buffer: DescriptorBuffer,
         region: RegionCopyBufferBuffer,
     },
+    ResolveImage {
+        src_image: DescriptorImage,
+        dst_image: DescriptorImage,
+        region: RegionResolveImage,
+    },
     ExecuteCommands {
         command_buffer: CommandBuffer,
     },
+    SetFramebufferExtent {
+        extent: Extent2<u32>,
+    },
     BindRenderTarget {
         render_target: RenderTarget,
     },
@@ -281,6 +515,32 @@ pub enum Command {
         render_area: RenderArea,
         color: Color,
     },
+    BindDepthStencilTarget {
+        depth_stencil_target: DepthStencilTarget,
+    },
+    UnbindDepthStencilTarget,
+    ClearDepthStencilTarget {
+        render_area: RenderArea,
+        depth: Option<f32>,
+        stencil: Option<u32>,
+    },
+    /// Clears attachment that is not bound as render target, e.g. resolve attachment.
+    ClearColorImage {
+        image: DescriptorImage,
+        format: Format,
+        samples: u32,
+        render_area: RenderArea,
+        color: Color,
+    },
+    /// Clears attachment that is not bound as depth/stencil target.
+    ClearDepthStencilImage {
+        image: DescriptorImage,
+        format: Format,
+        samples: u32,
+        render_area: RenderArea,
+        depth: Option<f32>,
+        stencil: Option<u32>,
+    },
     SetShaderState {
         shader_state: ShaderState,
     },
@@ -290,12 +550,61 @@ pub enum Command {
     SetInputAssemblyState {
         input_assembly_state: InputAssemblyState,
     },
+    SetTessellationState {
+        tessellation_state: TessellationState,
+    },
     SetViewportState {
         viewport_state: ViewportState,
     },
     SetRasterizationState {
         rasterization_state: RasterizationState,
     },
+    SetMultisampleState {
+        multisample_state: MultisampleState,
+    },
+    SetDepthStencilState {
+        depth_stencil_state: DepthStencilState,
+    },
+    SetColorBlendState {
+        color_blend_state: ColorBlendState,
+    },
+    SetDepthBias {
+        constant_factor: f32,
+        clamp: f32,
+        slope_factor: f32,
+    },
+    SetBlendConstants {
+        blend_constants: [f32; 4],
+    },
+    SetDynamicStates {
+        dynamic_states: DynamicStates,
+    },
+    SetViewports {
+        first_viewport: u32,
+        viewports: Vec<Viewport>,
+    },
+    SetScissors {
+        first_scissor: u32,
+        scissors: Vec<Scissor>,
+    },
+    SetLineWidth {
+        line_width: f32,
+    },
+    SetDepthBounds {
+        depth_bounds: Range2<f32>,
+    },
+    SetStencilCompareMask {
+        faces: StencilFaces,
+        compare_mask: u32,
+    },
+    SetStencilWriteMask {
+        faces: StencilFaces,
+        write_mask: u32,
+    },
+    SetStencilReference {
+        faces: StencilFaces,
+        reference: u32,
+    },
     BindVertexBuffer {
         vertex_buffer: VertexBuffer,
     },
@@ -315,6 +624,18 @@ pub enum Command {
         vertex_offset: i32,
         first_instance: u32,
     },
+    DrawPrimitiveIndirect {
+        buffer: DescriptorBuffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
+    DrawPrimitiveIndexedIndirect {
+        buffer: DescriptorBuffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    },
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -331,9 +652,84 @@ pub struct RegionCopyBufferImage {
     pub image_format: Format,
 }
 
+#[derive(Debug, Copy, Clone)]
+pub struct RegionResolveImage {
+    pub src_offset: Offset3<i32>,
+    pub dst_offset: Offset3<i32>,
+    pub extent: Extent3<u32>,
+    pub format: Format,
+    pub samples: u32,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct RegionCopyBufferBuffer {
     pub src_offset: u64,
     pub dst_offset: u64,
     pub size: u64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryHandleStore;
+    use common::graphics::MemoryBinding;
+
+    fn image(memory: &mut Memory, format: Format, samples: u32) -> DescriptorImage {
+        let size = samples as u64 * format.info().bytes_per_pixel as u64;
+        let mut binding = MemoryBinding::new();
+        binding.store(memory.allocate_memory(size), 0, size);
+        DescriptorImage {
+            binding,
+            extent: Extent3 {
+                width: 1,
+                height: 1,
+                depth: 1,
+            },
+        }
+    }
+
+    fn resolve(format: Format, samples: &[u8]) -> Vec<u8> {
+        let mut gpu = Gpu::new();
+        let src_image = image(&mut gpu.memory, format, 4);
+        let dst_image = image(&mut gpu.memory, format, 1);
+        gpu.memory.write_bytes(samples, &src_image.binding, 0);
+        gpu.resolve_image(
+            src_image,
+            dst_image.clone(),
+            RegionResolveImage {
+                src_offset: Default::default(),
+                dst_offset: Default::default(),
+                extent: dst_image.extent,
+                format,
+                samples: 4,
+            },
+        );
+        let size = format.info().bytes_per_pixel as u64;
+        gpu.memory.read_bytes(&dst_image.binding, 0, size).to_vec()
+    }
+
+    #[test]
+    fn resolve_averages_normalized_and_takes_first_integer_sample() {
+        let samples = [
+            [0, 0, 0, 0],
+            [255, 255, 0, 0],
+            [255, 0, 255, 0],
+            [0, 0, 0, 0],
+        ];
+        assert_eq!(
+            resolve(Format::R8G8B8A8Unorm, samples.as_flattened()),
+            [128, 64, 64, 0]
+        );
+
+        // Sample 0 with components that would change if averaged as unsigned or signed.
+        let samples = [
+            [7, 0x80, 0xff, 1],
+            [9, 0, 0, 0],
+            [9, 0x7f, 0x01, 0],
+            [9, 0, 0, 0],
+        ];
+        for format in [Format::R8G8B8A8Uint, Format::R8G8B8A8Sint] {
+            assert_eq!(resolve(format, samples.as_flattened()), [7, 0x80, 0xff, 1]);
+        }
+    }
+}
@@ -1,46 +1,76 @@
-use std::ops::{Index, IndexMut};
+use std::ops::{Index, IndexMut, Range};
 
-use crate::{draw_line_bresenham, draw_points, Memory};
+use crate::{
+    apply_provoking_vertex, assemble_lines, assemble_patches, assemble_quads, assemble_triangles,
+    blend, clip_line, clip_triangle, draw_line, draw_points, draw_triangle,
+    is_point_inside_view_volume, logic_op, Interpolations, Memory, PixelRect, TileAttachment,
+    TileGrid, WorkerPool,
+};
 use byteorder::ByteOrder;
 use common::{
     consts::{
-        MAX_VERTEX_ATTRIBUTE_OFFSET, MAX_VERTEX_BINDINGS, MAX_VERTEX_BINDING_STRIDE, MAX_VIEWPORTS,
+        MAX_COLOR_ATTACHMENTS, MAX_VERTEX_ATTRIBUTE_OFFSET, MAX_VERTEX_BINDINGS,
+        MAX_VERTEX_BINDING_STRIDE, MAX_VIEWPORTS,
     },
     graphics::{
-        CullMode, DescriptorImage, FrontFace, IndexBuffer, PolygonMode, VertexBuffer,
-        VertexInputRate, VertexInputState,
+        BlendFactor, BlendOp, CompareOp, CullMode, DescriptorBuffer, DescriptorImage, FrontFace,
+        IndexBuffer, LogicOp, PolygonMode, StencilOp, VertexBuffer, VertexInputRate,
+        VertexInputState,
+    },
+    math::{
+        Color, Extent2, Format, Fragment, FragmentQuad, Offset2, Position, Range2, Vector4, Vertex,
     },
-    math::{Color, Extent2, Format, Fragment, Offset2, Position, Range2, Vertex},
 };
 use hashbrown::HashMap;
 
 use log::warn;
-use shader::glsl::{FragmentShaderOutput, ShaderState, VertexShaderOutput};
+use shader::glsl::{FragmentShaderOutput, Shader, ShaderState, VertexShaderOutput};
 
 #[derive(Default)]
 pub struct GraphicsPipeline {
     render_targets: HashMap<RenderTargetIndex, RenderTarget>,
+    depth_stencil_target: Option<DepthStencilTarget>,
+    /// Extent of framebuffer, limits rendering also when it has no attachments.
+    framebuffer_extent: Option<Extent2<u32>>,
     vertex_buffers: [Option<VertexBuffer>; MAX_VERTEX_BINDINGS as usize],
     index_buffer: Option<IndexBuffer>,
 
     shader_state: ShaderState,
     vertex_input_state: VertexInputState,
     input_assembly_state: InputAssemblyState,
+    tessellation_state: TessellationState,
     viewport_state: ViewportState,
     rasterization_state: RasterizationState,
+    multisample_state: MultisampleState,
+    depth_stencil_state: DepthStencilState,
+    color_blend_state: ColorBlendState,
+    dynamic_states: DynamicStates,
+    dynamic_state_values: DynamicStateValues,
+
+    /// Persistent rasterizer threads shared by all draws.
+    worker_pool: WorkerPool,
 }
 
 impl GraphicsPipeline {
     pub fn new() -> Self {
         Self {
             render_targets: HashMap::default(),
+            depth_stencil_target: None,
+            framebuffer_extent: None,
             vertex_buffers: Default::default(),
             index_buffer: Default::default(),
             shader_state: Default::default(),
             vertex_input_state: Default::default(),
             input_assembly_state: Default::default(),
+            tessellation_state: Default::default(),
             viewport_state: Default::default(),
             rasterization_state: Default::default(),
+            multisample_state: Default::default(),
+            depth_stencil_state: Default::default(),
+            color_blend_state: Default::default(),
+            dynamic_states: Default::default(),
+            dynamic_state_values: Default::default(),
+            worker_pool: Default::default(),
         }
     }
 
@@ -52,6 +82,10 @@ impl GraphicsPipeline {
         self.render_targets.remove(&index);
     }
 
+    pub const fn set_framebuffer_extent(&mut self, extent: Extent2<u32>) {
+        self.framebuffer_extent = Some(extent);
+    }
+
     pub fn clear_render_target(
         &self,
         memory: &mut Memory,
@@ -63,22 +97,115 @@ impl GraphicsPipeline {
             .render_targets
             .get(&index)
             .unwrap_or_else(|| unreachable!());
-        assert_eq!(rt.samples, 1);
+        Self::clear_color_image(memory, &rt.image, rt.format, rt.samples, area, color);
+    }
+
+    /// Clears color `image` that is not bound as render target.
+    pub fn clear_color_image(
+        memory: &mut Memory,
+        image: &DescriptorImage,
+        format: Format,
+        samples: u32,
+        area: RenderArea,
+        color: Color,
+    ) {
+        Self::clear_image(
+            memory,
+            image,
+            format,
+            samples,
+            area,
+            &color.to_bytes(format),
+        );
+    }
+
+    pub fn bind_depth_stencil_target(&mut self, depth_stencil_target: DepthStencilTarget) {
+        self.depth_stencil_target = Some(depth_stencil_target);
+    }
+
+    pub fn unbind_depth_stencil_target(&mut self) {
+        self.depth_stencil_target = None;
+    }
+
+    /// Clears depth and/or stencil aspect of depth/stencil target, other aspect is preserved.
+    pub fn clear_depth_stencil_target(
+        &self,
+        memory: &mut Memory,
+        area: RenderArea,
+        depth: Option<f32>,
+        stencil: Option<u32>,
+    ) {
+        let target = self
+            .depth_stencil_target
+            .as_ref()
+            .unwrap_or_else(|| unreachable!());
+        Self::clear_depth_stencil_image(
+            memory,
+            &target.image,
+            target.format,
+            target.samples,
+            area,
+            depth,
+            stencil,
+        );
+    }
+
+    /// Clears depth and/or stencil aspect of `image` that is not bound as depth/stencil target.
+    pub fn clear_depth_stencil_image(
+        memory: &mut Memory,
+        image: &DescriptorImage,
+        format: Format,
+        samples: u32,
+        area: RenderArea,
+        depth: Option<f32>,
+        stencil: Option<u32>,
+    ) {
         assert!(area.offset.x >= 0);
         assert!(area.offset.y >= 0);
+        let depth = depth.filter(|_| format.has_depth());
+        let stencil = stencil.filter(|_| format.has_stencil());
 
-        let bytes_per_pixel = rt.format.info().bytes_per_pixel;
-        let dst_offset = rt.image.extent.width * area.offset.y as u32 * bytes_per_pixel as u32;
-        let mut dst = memory.get_memory_mut(&rt.image.binding);
-        dst = &mut dst[dst_offset as usize..];
-        let src = color.to_bytes(rt.format);
-        let src = src.as_slice();
-
-        for _y in 0..area.extent.height {
-            for _x in 0..area.extent.width {
-                let dst_offset = area.offset.x as usize * bytes_per_pixel as usize;
-                dst[dst_offset..dst_offset + bytes_per_pixel as usize].copy_from_slice(src);
-                dst = &mut dst[bytes_per_pixel as usize..];
+        let bytes_per_pixel = format.info().bytes_per_pixel as usize;
+        let dst = memory.get_memory_mut(&image.binding);
+        for y in 0..area.extent.height as u64 {
+            for x in 0..area.extent.width as u64 {
+                let x = area.offset.x as u64 + x;
+                let y = area.offset.y as u64 + y;
+                for sample in 0..samples {
+                    let offset = texel_offset(image, format, samples, x, y, sample) as usize;
+                    let texel = &mut dst[offset..offset + bytes_per_pixel];
+                    if let Some(depth) = depth {
+                        format.depth_to_bytes(depth, texel);
+                    }
+                    if let Some(stencil) = stencil {
+                        format.stencil_to_bytes(stencil as u8, texel);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Fills all samples of `image` texels within `area` with `src` bytes.
+    fn clear_image(
+        memory: &mut Memory,
+        image: &DescriptorImage,
+        format: Format,
+        samples: u32,
+        area: RenderArea,
+        src: &[u8],
+    ) {
+        assert!(area.offset.x >= 0);
+        assert!(area.offset.y >= 0);
+
+        let dst = memory.get_memory_mut(&image.binding);
+        for y in 0..area.extent.height as u64 {
+            for x in 0..area.extent.width as u64 {
+                let x = area.offset.x as u64 + x;
+                let y = area.offset.y as u64 + y;
+                for sample in 0..samples {
+                    let offset = texel_offset(image, format, samples, x, y, sample) as usize;
+                    dst[offset..offset + src.len()].copy_from_slice(src);
+                }
             }
         }
     }
@@ -87,22 +214,90 @@ impl GraphicsPipeline {
         self.shader_state = shader_state;
     }
 
-    pub fn set_vertex_input_state(&mut self, vertex_input_state: VertexInputState) {
+    pub const fn set_vertex_input_state(&mut self, vertex_input_state: VertexInputState) {
         self.vertex_input_state = vertex_input_state;
     }
 
-    pub fn set_input_assembly_state(&mut self, input_assembly_state: InputAssemblyState) {
+    pub const fn set_input_assembly_state(&mut self, input_assembly_state: InputAssemblyState) {
         self.input_assembly_state = input_assembly_state;
     }
 
-    pub fn set_viewport_state(&mut self, viewport_state: ViewportState) {
+    pub const fn set_tessellation_state(&mut self, tessellation_state: TessellationState) {
+        self.tessellation_state = tessellation_state;
+    }
+
+    pub const fn set_viewport_state(&mut self, viewport_state: ViewportState) {
         self.viewport_state = viewport_state;
     }
 
-    pub fn set_rasterization_state(&mut self, rasterization_state: RasterizationState) {
+    pub const fn set_rasterization_state(&mut self, rasterization_state: RasterizationState) {
         self.rasterization_state = rasterization_state;
     }
 
+    pub const fn set_multisample_state(&mut self, multisample_state: MultisampleState) {
+        self.multisample_state = multisample_state;
+    }
+
+    pub const fn set_depth_bias(&mut self, constant_factor: f32, clamp: f32, slope_factor: f32) {
+        self.dynamic_state_values.depth_bias = Some((constant_factor, clamp, slope_factor));
+    }
+
+    pub const fn set_depth_stencil_state(&mut self, depth_stencil_state: DepthStencilState) {
+        self.depth_stencil_state = depth_stencil_state;
+    }
+
+    pub const fn set_color_blend_state(&mut self, color_blend_state: ColorBlendState) {
+        self.color_blend_state = color_blend_state;
+    }
+
+    pub const fn set_blend_constants(&mut self, blend_constants: [f32; 4]) {
+        self.dynamic_state_values.blend_constants = Some(blend_constants);
+    }
+
+    pub const fn set_dynamic_states(&mut self, dynamic_states: DynamicStates) {
+        self.dynamic_states = dynamic_states;
+    }
+
+    pub fn set_viewports(&mut self, first_viewport: u32, viewports: Vec<Viewport>) {
+        for (i, viewport) in viewports.into_iter().enumerate() {
+            self.dynamic_state_values.viewports[ViewportIndex(first_viewport + i as u32)] =
+                Some(viewport);
+        }
+    }
+
+    pub fn set_scissors(&mut self, first_scissor: u32, scissors: Vec<Scissor>) {
+        for (i, scissor) in scissors.into_iter().enumerate() {
+            self.dynamic_state_values.scissors[ViewportIndex(first_scissor + i as u32)] =
+                Some(scissor);
+        }
+    }
+
+    pub const fn set_line_width(&mut self, line_width: f32) {
+        self.dynamic_state_values.line_width = Some(line_width);
+    }
+
+    pub const fn set_depth_bounds(&mut self, depth_bounds: Range2<f32>) {
+        self.dynamic_state_values.depth_bounds = Some(depth_bounds);
+    }
+
+    pub fn set_stencil_compare_mask(&mut self, faces: StencilFaces, compare_mask: u32) {
+        for values in self.dynamic_state_values.stencil_faces_mut(faces) {
+            values.compare_mask = Some(compare_mask);
+        }
+    }
+
+    pub fn set_stencil_write_mask(&mut self, faces: StencilFaces, write_mask: u32) {
+        for values in self.dynamic_state_values.stencil_faces_mut(faces) {
+            values.write_mask = Some(write_mask);
+        }
+    }
+
+    pub fn set_stencil_reference(&mut self, faces: StencilFaces, reference: u32) {
+        for values in self.dynamic_state_values.stencil_faces_mut(faces) {
+            values.reference = Some(reference);
+        }
+    }
+
     pub fn bind_vertex_buffer(&mut self, vertex_buffer: VertexBuffer) {
         let index = vertex_buffer.binding_number;
         self.vertex_buffers[index] = Some(vertex_buffer);
@@ -121,7 +316,7 @@ impl GraphicsPipeline {
         first_instance: u32,
     ) {
         // Fetch vertices from vertex buffer using bindings.
-        let vertices = self.fetch_vertex_input(
+        let (vertices, strips) = self.fetch_vertex_input(
             memory,
             vertex_count,
             instance_count,
@@ -129,7 +324,7 @@ impl GraphicsPipeline {
             first_instance,
         );
 
-        self.draw_primitive_rest(memory, vertices)
+        self.draw_primitive_rest(memory, vertices, &strips)
     }
 
     pub fn draw_primitive_indexed(
@@ -141,7 +336,7 @@ impl GraphicsPipeline {
         vertex_offset: i32,
         first_instance: u32,
     ) {
-        let vertices = self.fetch_vertex_input_indexed(
+        let (vertices, strips) = self.fetch_vertex_input_indexed(
             memory,
             index_count,
             instance_count,
@@ -150,10 +345,67 @@ impl GraphicsPipeline {
             first_instance,
         );
 
-        self.draw_primitive_rest(memory, vertices)
+        self.draw_primitive_rest(memory, vertices, &strips)
+    }
+
+    /// Draws using `draw_count` `VkDrawIndirectCommand` records read from `buffer` at execution
+    /// time.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdDrawIndirect
+    pub fn draw_primitive_indirect(
+        &mut self,
+        memory: &mut Memory,
+        buffer: DescriptorBuffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        for draw in 0..draw_count {
+            let [vertex_count, instance_count, first_vertex, first_instance] =
+                read_indirect_command(memory, &buffer, offset + draw as u64 * stride as u64);
+            self.draw_primitive(
+                memory,
+                vertex_count,
+                instance_count,
+                first_vertex,
+                first_instance,
+            );
+        }
+    }
+
+    /// Draws using `draw_count` `VkDrawIndexedIndirectCommand` records read from `buffer` at
+    /// execution time.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdDrawIndexedIndirect
+    pub fn draw_primitive_indexed_indirect(
+        &mut self,
+        memory: &mut Memory,
+        buffer: DescriptorBuffer,
+        offset: u64,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        for draw in 0..draw_count {
+            let [index_count, instance_count, first_index, vertex_offset, first_instance] =
+                read_indirect_command(memory, &buffer, offset + draw as u64 * stride as u64);
+            self.draw_primitive_indexed(
+                memory,
+                index_count,
+                instance_count,
+                first_index,
+                vertex_offset as i32,
+                first_instance,
+            );
+        }
     }
 
-    fn draw_primitive_rest(&mut self, memory: &mut Memory, vertices: Vec<Vertex>) {
+    /// Draws `vertices`, `strips` are vertex ranges separated by primitive restart.
+    fn draw_primitive_rest(
+        &mut self,
+        memory: &mut Memory,
+        vertices: Vec<Vertex>,
+        strips: &[Range<usize>],
+    ) {
+        self.apply_dynamic_states();
+
         // Vertex shader.
         let vertices = self.execute_vertex_shader(&self.vertex_input_state, vertices);
 
@@ -164,207 +416,312 @@ impl GraphicsPipeline {
         warn!("TODO: geometry assembler");
         warn!("TODO: geometry shader");
 
-        // Primitive assembler.
-        let Some(viewport) = self.viewport_state.viewports[ViewportIndex(0)].as_ref() else {
-            warn!("TODO: Use all set viewports");
-            unreachable!();
-        };
-        assert_eq!(viewport.offset.x, 0.0f32);
-        assert_eq!(viewport.offset.y, 0.0f32);
-        let primitive_vertices = vertices
+        // Rasterization.
+        // Color attachments of subpass are bound at index of their `Location`.
+        let render_targets = (0..MAX_COLOR_ATTACHMENTS as usize)
+            .filter_map(|index| self.render_targets.get(&RenderTargetIndex(index)).cloned())
+            .collect::<Vec<_>>();
+        // Fragments are generated only where framebuffer and all attachments are present.
+        let Some(framebuffer_extent) = render_targets
             .iter()
-            .map(|vertex_shader_output| {
-                let v = vertex_shader_output.position;
-
-                let x = v.get_as_sfloat32(0);
-                let y = v.get_as_sfloat32(1);
-                let z = v.get_as_sfloat32(2);
-                let w = v.get_as_sfloat32(3);
-                // Perspective division.
-                let x_ndc = x / w;
-                let y_ndc = y / w;
-                let z_ndc = z / w;
-                warn!("TODO: Depth test.");
-                warn!("TODO: Back-face culling.");
-                warn!("TODO: Clipping.");
-                // Viewport transformation
-                // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vertexpostproc-viewport\
-                assert_eq!(viewport.offset.x, 0.0);
-                assert_eq!(viewport.offset.y, 0.0);
-                let (p_x, p_y, p_z) = (
-                    viewport.extent.width,
-                    viewport.extent.height,
-                    viewport.depth.max - viewport.depth.min,
-                );
-                let (o_x, o_y, o_z) = (
-                    viewport.offset.x + viewport.extent.width / 2.0,
-                    viewport.offset.y + viewport.extent.height / 2.0,
-                    viewport.depth.min,
-                );
-                let (x_screen, y_screen, z_screen) = (
-                    (p_x / 2.0).mul_add(x_ndc, o_x),
-                    (p_y / 2.0).mul_add(y_ndc, o_y),
-                    p_z.mul_add(z_ndc, o_z),
-                );
-                Vertex {
-                    position: Position::from_sfloat32_raw(x_screen, y_screen, z_screen, 1.0),
-                    point_size: 1.0f32,
-                    index: vertex_shader_output.vertex_index,
-                    clip_distances: [0.0f32, 0.0f32, 0.0f32, 0.0f32],
-                }
+            .map(|rt| &rt.image)
+            .chain(
+                self.depth_stencil_target
+                    .as_ref()
+                    .map(|target| &target.image),
+            )
+            .map(|image| Extent2 {
+                width: image.extent.width,
+                height: image.extent.height,
             })
-            .collect::<Vec<_>>();
-
-        // Rasterization.
-        let Some(rt) = self.render_targets.get_mut(&RenderTargetIndex(0)).cloned() else {
-            warn!("TODO: Determine used RenderTarget from fragment shader");
+            .chain(self.framebuffer_extent)
+            .reduce(|a, b| Extent2 {
+                width: a.width.min(b.width),
+                height: a.height.min(b.height),
+            })
+        else {
             unreachable!()
         };
+        let samples = self.multisample_state.rasterization_samples;
+        for rt in &render_targets {
+            assert_eq!(rt.samples, samples);
+        }
+        let line_width = self.rasterization_state.line_width;
+        let interpolations = self.fragment_shader_input_interpolations();
+        let depth_clamp = self.rasterization_state.depth_clamp_enable;
 
-        warn!("TODO: Determine color in vertex shader");
-        warn!("TODO: Color interpolation");
-        let color = Color::from_sfloat32_raw(1.0f32, 1.0f32, 1.0f32, 1.0f32);
-
-        let mut fragments = vec![];
-        match self.input_assembly_state.topology {
-            PrimitiveTopology::PointList => draw_points(primitive_vertices, &mut fragments, color),
-            PrimitiveTopology::LineList => unimplemented!(),
-            PrimitiveTopology::LineStrip => unimplemented!(),
-            PrimitiveTopology::TriangleList => {
-                assert_eq!(primitive_vertices.len() % 3, 0);
-                for triangle in primitive_vertices.chunks_exact(3) {
-                    let vertices: [Vertex; 3] =
-                        triangle.try_into().unwrap_or_else(|_| unreachable!());
-                    match self.rasterization_state.polygon_mode {
-                        PolygonMode::Fill | PolygonMode::Line => {
-                            warn!("TODO: Implement PolygonMode::Fill");
-                            for i in 0..3 {
-                                draw_line_bresenham(
-                                    vertices[i],
-                                    vertices[(i + 1) % 3],
-                                    &mut fragments,
-                                    color,
-                                );
-                            }
-                        }
-                        PolygonMode::Point => {
-                            draw_points(vertices, &mut fragments, color);
-                        }
-                        PolygonMode::FillRectangle => unimplemented!(),
+        // Primitive assembler, clipping and viewport transformation.
+        // Viewport and scissor are selected by provoking vertex of each primitive.
+        let mut primitives = vec![];
+        let topology = self.input_assembly_state.topology;
+        match topology {
+            PrimitiveTopology::PointList => {
+                for vertex in vertices
+                    .iter()
+                    .filter(|vertex| is_point_inside_view_volume(vertex, depth_clamp))
+                {
+                    let viewport_index = self.viewport_index(vertex.viewport_index);
+                    primitives.push(Primitive::point(
+                        self.viewport(viewport_index).transform(vertex),
+                        viewport_index,
+                    ));
+                }
+            }
+            PrimitiveTopology::LineList
+            | PrimitiveTopology::LineStrip
+            | PrimitiveTopology::LineListWithAdjacency
+            | PrimitiveTopology::LineStripWithAdjacency => {
+                for line in assemble_lines(topology, &vertices, strips) {
+                    let Some(mut clipped) = clip_line(line, depth_clamp, &interpolations) else {
+                        continue;
                     };
+                    apply_provoking_vertex(&mut clipped, &line[0], &interpolations);
+                    let viewport_index = self.viewport_index(line[0].viewport_index);
+                    let viewport = self.viewport(viewport_index);
+                    primitives.push(Primitive::line(
+                        clipped.map(|vertex| viewport.transform(&vertex)),
+                        viewport_index,
+                    ));
+                }
+            }
+            PrimitiveTopology::TriangleList
+            | PrimitiveTopology::TriangleStrip
+            | PrimitiveTopology::TriangleFan
+            | PrimitiveTopology::TriangleListWithAdjacency
+            | PrimitiveTopology::TriangleStripWithAdjacency => {
+                for (provoking_vertex, mut triangle) in
+                    assemble_triangles(topology, &vertices, strips)
+                        .into_iter()
+                        .flat_map(|triangle| {
+                            clip_triangle(triangle, depth_clamp, &interpolations)
+                                .into_iter()
+                                .map(move |clipped| (triangle[0], clipped))
+                        })
+                {
+                    // Clipping may introduce new vertices, flat varyings still come from
+                    // provoking vertex of original primitive.
+                    apply_provoking_vertex(&mut triangle, &provoking_vertex, &interpolations);
+                    let viewport_index = self.viewport_index(provoking_vertex.viewport_index);
+                    let viewport = self.viewport(viewport_index);
+                    let mut vertices = triangle.map(|vertex| viewport.transform(&vertex));
+                    let front_facing = self.rasterization_state.is_front_facing(&vertices);
+                    if self.rasterization_state.is_culled(front_facing) {
+                        continue;
+                    }
+                    if let Some(target) = self.depth_stencil_target.as_ref() {
+                        self.rasterization_state
+                            .apply_depth_bias(&mut vertices, target.format);
+                    }
+                    primitives.push(Primitive {
+                        kind: PrimitiveKind::Triangle,
+                        vertices,
+                        viewport_index,
+                        front_facing,
+                    });
                 }
             }
-            PrimitiveTopology::TriangleStrip => unimplemented!(),
-            PrimitiveTopology::TriangleFan => unimplemented!(),
-            PrimitiveTopology::LineListWithAdjacency => unimplemented!(),
-            PrimitiveTopology::LineStripWithAdjacency => unimplemented!(),
-            PrimitiveTopology::TriangleListWithAdjacency => unimplemented!(),
-            PrimitiveTopology::TriangleStripWithAdjacency => unimplemented!(),
-            PrimitiveTopology::PatchList => unimplemented!(),
+            PrimitiveTopology::PatchList => {
+                let patches = assemble_patches(
+                    &vertices,
+                    strips,
+                    self.tessellation_state.patch_control_points,
+                );
+                Self::tessellate(&patches);
+            }
         };
 
-        warn!("TODO: early per-fragment operations");
+        // Binning of primitives into screen tiles.
+        let mut tile_grid = TileGrid::new(framebuffer_extent);
+        for (index, primitive) in primitives.iter().enumerate() {
+            tile_grid.bin(index, primitive.bounds(line_width));
+        }
+
+        // Rasterization, fragment shading and depth/stencil tests of tiles in parallel.
+        // Tiles cover disjoint pixels and keep primitive order, so the result does not depend
+        // on number of workers.
+        let tiles = tile_grid.into_tiles();
+        let early_fragment_tests = self.fragment_shader().early_fragment_tests();
+        let shaded_tiles = {
+            let memory: &Memory = memory;
+            self.worker_pool.parallel_map(&tiles, |tile| {
+                let mut quads = vec![];
+                for &index in &tile.primitives {
+                    self.rasterize_primitive(
+                        &primitives[index],
+                        tile.rect,
+                        samples,
+                        &interpolations,
+                        &mut quads,
+                    );
+                }
+
+                // Sample mask test.
+                // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-samplemask
+                // Fragments with no samples left remain in quad as helper invocations.
+                for fragment in quads.iter_mut().flatten() {
+                    fragment.sample_mask &= self.multisample_state.sample_mask;
+                }
+
+                let mut depth_stencil = self.depth_stencil_target.as_ref().map(|target| {
+                    TileAttachment::load(
+                        memory,
+                        &target.image,
+                        target.format,
+                        target.samples,
+                        tile.rect,
+                    )
+                });
+
+                // Early per-fragment operations.
+                // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-early
+                if let Some(attachment) = depth_stencil.as_mut().filter(|_| early_fragment_tests) {
+                    for fragment in quads
+                        .iter_mut()
+                        .flatten()
+                        .filter(|fragment| !fragment.is_helper_invocation())
+                    {
+                        fragment.sample_mask =
+                            self.depth_stencil_test(attachment, &(*fragment).into());
+                    }
+                }
+                quads.retain(|quad| !quad.iter().all(Fragment::is_helper_invocation));
+
+                // Fragment shader, discarded fragments are not returned.
+                let mut fragments = self.execute_fragment_shader(quads);
 
-        // Fragment shader.
-        let fragments = self.execute_fragment_shader(fragments);
+                // Late per-fragment operations.
+                if let Some(attachment) = depth_stencil.as_mut().filter(|_| !early_fragment_tests) {
+                    for fragment in &mut fragments {
+                        fragment.sample_mask = self.depth_stencil_test(attachment, fragment);
+                    }
+                    fragments.retain(|fragment| fragment.sample_mask != 0);
+                }
+                (fragments, depth_stencil)
+            })
+        };
 
-        warn!("TODO: late per-fragment operations");
-        warn!("TODO: color/blending operations");
+        // Depth/stencil attachment output, tile by tile.
+        let mut fragments = vec![];
+        for (tile_fragments, depth_stencil) in shaded_tiles {
+            if let (Some(attachment), Some(target)) =
+                (depth_stencil, self.depth_stencil_target.as_ref())
+            {
+                attachment.store(memory, &target.image);
+            }
+            fragments.extend(tile_fragments);
+        }
 
-        // Color attachment output
-        warn!("TODO: Fragment shader should write directly to render target");
+        // Color blending and color attachment output, for each covered sample.
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#interfaces-fragmentoutput
         for fragment in fragments {
-            let position = fragment.position;
-            let color = fragment.color.to_bytes(rt.format);
-
-            let framebuffer_width = rt.image.extent.width as u64;
-            let framebuffer_height = rt.image.extent.height as u64;
-            let framebuffer_x = position.get_as_sfloat32(0) as u64;
-            let framebuffer_y = position.get_as_sfloat32(1) as u64;
-            assert!(framebuffer_x < framebuffer_width);
-            assert!(framebuffer_y < framebuffer_height);
-            let dst_offset = (framebuffer_x + framebuffer_y * framebuffer_width)
-                * rt.format.info().bytes_per_pixel as u64;
-            warn!("TODO: Write texel to image function");
-            memory.write_bytes(&color, &rt.image.binding, dst_offset);
+            for rt in &render_targets {
+                let Some(color) = fragment.colors[rt.index.0] else {
+                    continue;
+                };
+                self.color_output(memory, rt, &fragment, color);
+            }
         }
     }
 }
 
 impl GraphicsPipeline {
+    /// Blends `color` output of `fragment` with covered samples of `rt` and writes them,
+    /// converting to format of attachment.
+    fn color_output(
+        &self,
+        memory: &mut Memory,
+        rt: &RenderTarget,
+        fragment: &FragmentShaderOutput,
+        color: Color,
+    ) {
+        let blend_state = self.color_blend_state.attachments[rt.index.0].unwrap_or_default();
+        let bytes_per_pixel = rt.format.info().bytes_per_pixel as u64;
+        let position = fragment.position;
+
+        let framebuffer_width = rt.image.extent.width as u64;
+        let framebuffer_height = rt.image.extent.height as u64;
+        let framebuffer_x = position.get_as_sfloat32(0) as u64;
+        let framebuffer_y = position.get_as_sfloat32(1) as u64;
+        assert!(framebuffer_x < framebuffer_width);
+        assert!(framebuffer_y < framebuffer_height);
+        for sample in (0..rt.samples).filter(|sample| fragment.sample_mask & (1 << sample) != 0) {
+            let dst_offset = texel_offset(
+                &rt.image,
+                rt.format,
+                rt.samples,
+                framebuffer_x,
+                framebuffer_y,
+                sample,
+            );
+            let dst_bytes = memory.read_bytes(&rt.image.binding, dst_offset, bytes_per_pixel);
+            // NOTE: Logical operations apply to all formats except floating-point and sRGB ones.
+            let info = rt.format.info();
+            let logic_op_enable = self.color_blend_state.logic_op_enable
+                && (info.is_unorm || info.is_snorm || info.is_integer);
+            let bytes = if logic_op_enable || info.is_integer {
+                // NOTE: Integer formats are never blended, their raw bits are copied instead.
+                let op = if logic_op_enable {
+                    self.color_blend_state.logic_op
+                } else {
+                    LogicOp::Copy
+                };
+                logic_op(
+                    op,
+                    blend_state.color_write_mask,
+                    rt.format,
+                    &color.to_bytes(rt.format),
+                    dst_bytes,
+                )
+            } else {
+                // NOTE: Logical operation disables blending, even for formats it does not support.
+                let blend_state = ColorBlendAttachmentState {
+                    blend_enable: blend_state.blend_enable
+                        && !self.color_blend_state.logic_op_enable,
+                    ..blend_state
+                };
+                let clamp = if info.is_unorm {
+                    Some((0.0, 1.0))
+                } else if info.is_snorm {
+                    Some((-1.0, 1.0))
+                } else {
+                    None
+                };
+                let dst = Color::from_vertex_buffer_bytes(rt.format, dst_bytes);
+                let [r, g, b, a] = blend(
+                    &blend_state,
+                    self.color_blend_state.blend_constants,
+                    color.get_as_f32_array(),
+                    dst.get_as_f32_array(),
+                    clamp,
+                );
+                Color::from_sfloat32_raw(r, g, b, a).to_bytes(rt.format)
+            };
+            warn!("TODO: Write texel to image function");
+            memory.write_bytes(&bytes, &rt.image.binding, dst_offset);
+        }
+    }
+
+    /// Fetches vertices of all instances, each instance is assembled as separate strip.
     fn fetch_vertex_input(
-        &mut self,
+        &self,
         memory: &Memory,
         vertex_count: u32,
         instance_count: u32,
         first_vertex: u32,
         first_instance: u32,
-    ) -> Vec<Vertex> {
-        assert_eq!(instance_count, 1);
-        assert_eq!(first_vertex, 0);
-        assert_eq!(first_instance, 0);
-
-        if self.vertex_input_state.bindings.iter().all(|x| x.is_none()) {
-            return (first_vertex..vertex_count)
-                .map(|index| Vertex {
-                    position: Default::default(),
-                    point_size: 1.0f32,
-                    index,
-                    clip_distances: Default::default(),
-                })
-                .collect();
-        }
-
-        let Some(binding) = self.vertex_input_state.bindings[0].as_ref() else {
-            warn!("TODO: Determine used VertexBindings from vertex shader (if any)");
-            unreachable!("{:#?}", self.vertex_input_state.bindings)
-        };
-        assert!(binding.stride < MAX_VERTEX_BINDING_STRIDE);
-        assert_eq!(binding.input_rate, VertexInputRate::Vertex);
-
-        let Some(attribute) = self.vertex_input_state.attributes[0].as_ref() else {
-            warn!("TODO: Determine used VertexAttributes from vertex shader (if any)");
-            unreachable!()
-        };
-        assert_eq!(attribute.binding, binding.number);
-        assert_eq!(attribute.location, 0);
-        assert!(attribute.offset < MAX_VERTEX_ATTRIBUTE_OFFSET);
-
-        // Create elements from vertex buffer using attributes.
-        let Some(vertex_buffer) = self.vertex_buffers[binding.number].as_ref() else {
-            unreachable!()
-        };
-        let element_format = attribute.format;
-        let element_size = element_format.info().bytes_per_pixel as u32;
-        let element_stride = if binding.stride == 0 {
-            element_size
-        } else {
-            binding.stride
-        };
-        let vertex_buffer_size = vertex_buffer.buffer.binding.size - vertex_buffer.offset;
-        assert_eq!(vertex_buffer_size % element_stride as u64, 0);
-
-        let bytes = memory
-            .read_bytes(
-                &vertex_buffer.buffer.binding,
-                vertex_buffer.offset,
-                vertex_buffer_size,
-            )
-            .to_vec();
-        warn!("TODO: Stream vertex buffer bytes instead of reading all of them?");
-
-        warn!("TODO: Determine vertex element components in shader?");
-        let vertices = bytes
-            .chunks_exact(element_stride as usize)
-            .take(vertex_count as usize)
-            .map(|element| Vertex {
-                position: Position::from_vertex_buffer_bytes(element_format, element),
-                point_size: 1.0f32,
-                index: 0,
-                clip_distances: Default::default(),
-            })
-            .collect();
-        vertices
+    ) -> (Vec<Vertex>, Vec<Range<usize>>) {
+        let mut vertices = vec![];
+        let mut strips = vec![];
+        // Vertex and instance indices wrap around instead of overflowing.
+        for instance in 0..instance_count {
+            let instance_index = first_instance.wrapping_add(instance);
+            let strip_start = vertices.len();
+            vertices.extend((0..vertex_count).map(|vertex| {
+                self.fetch_vertex(memory, first_vertex.wrapping_add(vertex), instance_index)
+            }));
+            strips.push(strip_start..vertices.len());
+        }
+        (vertices, strips)
     }
 
     fn fetch_vertex_input_indexed(
@@ -373,43 +730,99 @@ impl GraphicsPipeline {
         index_count: u32,
         instance_count: u32,
         first_index: u32,
-        _vertex_offset: i32,
+        vertex_offset: i32,
         first_instance: u32,
-    ) -> Vec<Vertex> {
-        assert_eq!(instance_count, 1);
-        assert_eq!(first_index, 0);
-        assert_eq!(first_instance, 0);
-
+    ) -> (Vec<Vertex>, Vec<Range<usize>>) {
         let Some(index_buffer) = self.index_buffer.as_ref() else {
             unreachable!()
         };
+        let restart_index = self
+            .input_assembly_state
+            .primitive_restart
+            .then(|| index_buffer.primitive_restart_index());
 
-        self.vertex_input_state.bindings[0].as_ref().map_or_else(
-            || {
-                let mut vertices = vec![];
-                for index in first_index..first_index + index_count {
-                    let bytes = memory.read_bytes(
-                        &index_buffer.buffer.binding,
-                        index_buffer.offset + index as u64 * index_buffer.index_size as u64,
-                        index_buffer.index_size as u64,
-                    );
-                    let index =
-                        byteorder::NativeEndian::read_uint(bytes, index_buffer.index_size as usize)
-                            as u32;
-                    vertices.push(Vertex {
-                        position: Default::default(),
-                        point_size: 1.0f32,
-                        index,
-                        clip_distances: Default::default(),
-                    });
+        let mut vertices = vec![];
+        let mut strips = vec![];
+        // Instance indices wrap around, positions in index buffer are computed without overflow.
+        for instance in 0..instance_count {
+            let instance_index = first_instance.wrapping_add(instance);
+            let mut strip_start = vertices.len();
+            for index in 0..index_count {
+                let size = index_buffer.index_size as u64;
+                let offset = index_buffer.offset + (first_index as u64 + index as u64) * size;
+                // Indices outside of index buffer are read as zero.
+                let index = if offset + size <= index_buffer.buffer.binding.size {
+                    let bytes = memory.read_bytes(&index_buffer.buffer.binding, offset, size);
+                    byteorder::NativeEndian::read_uint(bytes, index_buffer.index_size as usize)
+                        as u32
+                } else {
+                    0
+                };
+                if Some(index) == restart_index {
+                    strips.push(strip_start..vertices.len());
+                    strip_start = vertices.len();
+                    continue;
                 }
-                vertices
-            },
-            |_binding| {
-                warn!("TODO: Determine used VertexBindings from vertex shader (if any)");
-                todo!();
-            },
-        )
+                // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vkCmdDrawIndexed
+                let index = index.wrapping_add_signed(vertex_offset);
+                vertices.push(self.fetch_vertex(memory, index, instance_index));
+            }
+            strips.push(strip_start..vertices.len());
+        }
+        (vertices, strips)
+    }
+
+    /// Fetches all vertex attributes of vertex `index` of instance `instance_index` from bound
+    /// vertex buffers.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fxvertex-input-address-calculation
+    fn fetch_vertex(&self, memory: &Memory, index: u32, instance_index: u32) -> Vertex {
+        let mut vertex = Vertex {
+            position: Default::default(),
+            point_size: 1.0f32,
+            index,
+            instance_index,
+            clip_distances: Default::default(),
+            attributes: Default::default(),
+        };
+
+        for attribute in self.vertex_input_state.attributes.iter().flatten() {
+            assert!(attribute.offset < MAX_VERTEX_ATTRIBUTE_OFFSET);
+            let Some(Some(binding)) = self
+                .vertex_input_state
+                .bindings
+                .get(attribute.binding.0 as usize)
+            else {
+                unreachable!("{:#?}", self.vertex_input_state.bindings)
+            };
+            assert!(binding.stride < MAX_VERTEX_BINDING_STRIDE);
+
+            let Some(vertex_buffer) = self.vertex_buffers[binding.number].as_ref() else {
+                unreachable!()
+            };
+            let element_format = attribute.format;
+            let element_size = element_format.info().bytes_per_pixel as u32;
+            // NOTE: Stride 0 makes all vertices or instances read the same element.
+            let element_stride = binding.stride;
+            let element_index = match binding.input_rate {
+                VertexInputRate::Vertex => index,
+                VertexInputRate::Instance => instance_index,
+            };
+            let offset = vertex_buffer.offset
+                + element_index as u64 * element_stride as u64
+                + attribute.offset as u64;
+            // Attributes outside of vertex buffer are read as zero.
+            if offset + element_size as u64 > vertex_buffer.buffer.binding.size {
+                continue;
+            }
+            let bytes =
+                memory.read_bytes(&vertex_buffer.buffer.binding, offset, element_size as u64);
+
+            let Some(value) = vertex.attributes.get_mut(attribute.location as usize) else {
+                unreachable!()
+            };
+            *value = Vector4::from_vertex_buffer_bytes(element_format, bytes);
+        }
+        vertex
     }
 
     fn execute_vertex_shader(
@@ -426,58 +839,451 @@ impl GraphicsPipeline {
         shader.execute_vertex_shader(vertex_input_state, vertices)
     }
 
-    fn execute_fragment_shader(&self, fragments: Vec<Fragment>) -> Vec<FragmentShaderOutput> {
+    fn fragment_shader_input_interpolations(&self) -> Interpolations {
         let shader = self
             .shader_state
             .fragment_shader
             .as_ref()
             .unwrap_or_else(|| unreachable!());
-        shader.execute_fragment_shader(fragments)
+        Interpolations {
+            modes: shader.input_interpolations(),
+            centroid: shader.input_centroids(),
+            inputs: shader.input_locations(),
+        }
     }
-}
 
-#[derive(Debug, Copy, Clone, Default)]
-pub struct RenderArea {
-    pub extent: Extent2<u32>,
-    pub offset: Offset2<i32>,
-}
+    /// Overrides states of bound pipeline that are dynamic with values set by dynamic state
+    /// commands. Pipeline binding restores static states, so this is done before every draw.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#pipelines-dynamic-state
+    fn apply_dynamic_states(&mut self) {
+        let dynamic_states = self.dynamic_states;
+        let values = &self.dynamic_state_values;
+        if dynamic_states.viewport {
+            self.viewport_state.viewports = values.viewports;
+        }
+        if dynamic_states.scissor {
+            self.viewport_state.scissors = values.scissors.clone();
+        }
+        if dynamic_states.line_width {
+            if let Some(line_width) = values.line_width {
+                self.rasterization_state.line_width = line_width;
+            }
+        }
+        if dynamic_states.depth_bias {
+            if let Some((constant_factor, clamp, slope_factor)) = values.depth_bias {
+                self.rasterization_state.depth_bias_constant_factor = constant_factor;
+                self.rasterization_state.depth_bias_clamp = clamp;
+                self.rasterization_state.depth_bias_slope_factor = slope_factor;
+            }
+        }
+        if dynamic_states.blend_constants {
+            if let Some(blend_constants) = values.blend_constants {
+                self.color_blend_state.blend_constants = blend_constants;
+            }
+        }
+        if dynamic_states.depth_bounds {
+            if let Some(depth_bounds) = values.depth_bounds {
+                self.depth_stencil_state.depth_bounds = depth_bounds;
+            }
+        }
+        for (state, values) in [
+            (&mut self.depth_stencil_state.front, &values.stencil_front),
+            (&mut self.depth_stencil_state.back, &values.stencil_back),
+        ] {
+            if let (true, Some(compare_mask)) =
+                (dynamic_states.stencil_compare_mask, values.compare_mask)
+            {
+                state.compare_mask = compare_mask;
+            }
+            if let (true, Some(write_mask)) = (dynamic_states.stencil_write_mask, values.write_mask)
+            {
+                state.write_mask = write_mask;
+            }
+            if let (true, Some(reference)) = (dynamic_states.stencil_reference, values.reference) {
+                state.reference = reference;
+            }
+        }
+    }
 
-#[derive(Debug, Clone)]
-pub struct RenderTarget {
-    pub index: RenderTargetIndex,
-    pub format: Format,
-    pub samples: u32,
-    pub image: DescriptorImage,
-}
+    /// Validates `ViewportIndex` written by shader, indices of unset viewports select viewport 0.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vertexpostproc-viewport
+    fn viewport_index(&self, index: u32) -> ViewportIndex {
+        match self.viewport_state.viewports.get(index as usize) {
+            Some(Some(_)) => ViewportIndex(index),
+            _ => ViewportIndex(0),
+        }
+    }
 
-#[derive(Eq, Hash, PartialEq, Debug, Copy, Clone)]
-pub struct RenderTargetIndex(pub usize);
+    fn viewport(&self, index: ViewportIndex) -> &Viewport {
+        let Some(viewport) = self.viewport_state.viewports[index].as_ref() else {
+            unreachable!("viewport {:?} is not set", index)
+        };
+        viewport
+    }
 
-#[derive(Debug, Clone, Default)]
-pub struct InputAssemblyState {
-    pub topology: PrimitiveTopology,
-    pub primitive_restart: bool,
-}
+    /// Rasterizes `primitive` into quads of fragments covering pixels within `bounds`.
+    fn rasterize_primitive(
+        &self,
+        primitive: &Primitive,
+        bounds: PixelRect,
+        samples: u32,
+        interpolations: &Interpolations,
+        quads: &mut Vec<FragmentQuad>,
+    ) {
+        // Guard band leaves primitives extending past viewport unclipped, fragments are generated
+        // only within it as if primitives were clipped to view volume exactly.
+        let viewport = self.viewport(primitive.viewport_index).pixel_rect();
+        let Some(bounds) = bounds.intersection(&viewport) else {
+            return;
+        };
+        let line_width = self.rasterization_state.line_width;
+        let mut fragments = vec![];
+        let vertices = primitive.vertices;
+        match primitive.kind {
+            PrimitiveKind::Point => {
+                draw_points([vertices[0]], bounds, samples, &mut fragments);
+            }
+            PrimitiveKind::Line => {
+                draw_line(
+                    [vertices[0], vertices[1]],
+                    line_width,
+                    bounds,
+                    samples,
+                    interpolations,
+                    &mut fragments,
+                );
+            }
+            PrimitiveKind::Triangle => match self.rasterization_state.polygon_mode {
+                PolygonMode::Fill => {
+                    draw_triangle(vertices, bounds, samples, interpolations, &mut fragments);
+                }
+                PolygonMode::Line => {
+                    for i in 0..3 {
+                        draw_line(
+                            [vertices[i], vertices[(i + 1) % 3]],
+                            line_width,
+                            bounds,
+                            samples,
+                            interpolations,
+                            &mut fragments,
+                        );
+                    }
+                }
+                PolygonMode::Point => {
+                    draw_points(vertices, bounds, samples, &mut fragments);
+                }
+                PolygonMode::FillRectangle => unimplemented!(),
+            },
+        }
+        self.finish_primitive(
+            &mut fragments,
+            primitive.viewport_index,
+            primitive.front_facing,
+        );
+        quads.extend(assemble_quads(fragments));
+    }
 
-#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
-pub enum PrimitiveTopology {
-    #[default]
-    PointList,
-    LineList,
-    LineStrip,
-    TriangleList,
-    TriangleStrip,
-    TriangleFan,
-    LineListWithAdjacency,
-    LineStripWithAdjacency,
-    TriangleListWithAdjacency,
-    TriangleStripWithAdjacency,
-    PatchList,
-}
+    /// Applies facing, depth clamp and scissor test to `fragments` of single primitive.
+    /// Fragments failing scissor test become helper invocations.
+    fn finish_primitive(
+        &self,
+        fragments: &mut [Fragment],
+        viewport_index: ViewportIndex,
+        front_facing: bool,
+    ) {
+        let depth = self.viewport(viewport_index).depth;
+        let (min_depth, max_depth) = (depth.min.min(depth.max), depth.min.max(depth.max));
+        let scissor = self.viewport_state.scissors[viewport_index].as_ref();
+        for fragment in fragments {
+            if scissor.is_some_and(|scissor| !scissor.contains(fragment)) {
+                fragment.sample_mask = 0;
+            }
+            fragment.front_facing = front_facing;
+            // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-depth
+            if self.rasterization_state.depth_clamp_enable {
+                for depth in &mut fragment.sample_depths {
+                    *depth = depth.clamp(min_depth, max_depth);
+                }
+            }
+        }
+    }
 
-#[derive(Debug, Clone, Default)]
-pub struct ViewportState {
-    pub viewports: [Option<Viewport>; MAX_VIEWPORTS as usize],
+    /// Stub of tessellation stage, `patches` are not tessellated into primitives and draw
+    /// nothing until tessellation shaders are supported.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#tessellation
+    fn tessellate(patches: &[Vec<VertexShaderOutput>]) {
+        warn!(
+            "TODO: Tessellate {} patches, they are not drawn",
+            patches.len()
+        );
+    }
+
+    fn fragment_shader(&self) -> &Shader {
+        self.shader_state
+            .fragment_shader
+            .as_ref()
+            .unwrap_or_else(|| unreachable!())
+    }
+
+    fn execute_fragment_shader(&self, quads: Vec<FragmentQuad>) -> Vec<FragmentShaderOutput> {
+        self.fragment_shader().execute_fragment_shader(quads)
+    }
+
+    /// Performs stencil and depth tests of `fragment` samples against tile copy of depth/stencil
+    /// attachment, updating it according to stencil operations and depth write enable.
+    /// Returns mask of samples which passed the tests.
+    fn depth_stencil_test(
+        &self,
+        attachment: &mut TileAttachment,
+        fragment: &FragmentShaderOutput,
+    ) -> u32 {
+        let state = &self.depth_stencil_state;
+        let format = attachment.format();
+        let stencil_test_enable = state.stencil_test_enable && format.has_stencil();
+        let depth_test_enable = state.depth_test_enable && format.has_depth();
+        let depth_bounds_test_enable = state.depth_bounds_test_enable && format.has_depth();
+        if !stencil_test_enable && !depth_test_enable && !depth_bounds_test_enable {
+            return fragment.sample_mask;
+        }
+
+        let x = fragment.position.get_as_sfloat32(0) as i64;
+        let y = fragment.position.get_as_sfloat32(1) as i64;
+        (0..attachment.samples())
+            .filter(|sample| fragment.sample_mask & (1 << sample) != 0)
+            .filter(|&sample| {
+                self.depth_stencil_test_sample(
+                    attachment.texel_mut(x, y, sample),
+                    format,
+                    fragment,
+                    sample,
+                    stencil_test_enable,
+                    depth_test_enable,
+                )
+            })
+            .fold(0, |mask, sample| mask | (1 << sample))
+    }
+
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-stencil
+    fn depth_stencil_test_sample(
+        &self,
+        texel: &mut [u8],
+        format: Format,
+        fragment: &FragmentShaderOutput,
+        sample: u32,
+        stencil_test_enable: bool,
+        depth_test_enable: bool,
+    ) -> bool {
+        let state = &self.depth_stencil_state;
+
+        // Points and lines are always front-facing.
+        let stencil_state = if fragment.front_facing {
+            &state.front
+        } else {
+            &state.back
+        };
+        let update_stencil = |texel: &mut [u8], op: StencilOp| {
+            if stencil_test_enable {
+                let stored = format.stencil_from_bytes(texel);
+                let value = op.apply(stored, stencil_state.reference as u8);
+                let write_mask = stencil_state.write_mask as u8;
+                format.stencil_to_bytes((stored & !write_mask) | (value & write_mask), texel);
+            }
+        };
+
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-dbt
+        if state.depth_bounds_test_enable && format.has_depth() {
+            let stored = format.depth_from_bytes(texel);
+            if !(state.depth_bounds.min..=state.depth_bounds.max).contains(&stored) {
+                return false;
+            }
+        }
+
+        if stencil_test_enable {
+            let compare_mask = stencil_state.compare_mask as u8;
+            let stored = format.stencil_from_bytes(texel);
+            if !stencil_state.compare_op.compare(
+                stencil_state.reference as u8 & compare_mask,
+                stored & compare_mask,
+            ) {
+                update_stencil(texel, stencil_state.fail_op);
+                return false;
+            }
+        }
+
+        // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-depth
+        if depth_test_enable {
+            // Depth is clamped during rasterization if depth clamp is enabled.
+            let depth = fragment.sample_depths[sample as usize];
+            // Fragment depth is converted to depth attachment format before comparison.
+            let mut converted = texel.to_vec();
+            format.depth_to_bytes(depth, &mut converted);
+            if !state.depth_compare_op.compare(
+                format.depth_from_bytes(&converted),
+                format.depth_from_bytes(texel),
+            ) {
+                update_stencil(texel, stencil_state.depth_fail_op);
+                return false;
+            }
+            if state.depth_write_enable {
+                format.depth_to_bytes(depth, texel);
+            }
+        }
+
+        update_stencil(texel, stencil_state.pass_op);
+        true
+    }
+}
+
+/// Reads indirect draw command consisting of `N` 32-bit values at `offset` of `buffer`.
+/// Commands outside of buffer are read as zero, so they draw nothing.
+fn read_indirect_command<const N: usize>(
+    memory: &Memory,
+    buffer: &DescriptorBuffer,
+    offset: u64,
+) -> [u32; N] {
+    let size = (N * std::mem::size_of::<u32>()) as u64;
+    let mut values = [0u32; N];
+    if offset
+        .checked_add(size)
+        .is_some_and(|end| end <= buffer.binding.size)
+    {
+        let bytes = memory.read_bytes(&buffer.binding, offset, size);
+        byteorder::NativeEndian::read_u32_into(bytes, &mut values);
+    }
+    values
+}
+
+/// Byte offset of `sample` of texel (x, y) in `image`, samples of texel are stored consecutively.
+pub(crate) const fn texel_offset(
+    image: &DescriptorImage,
+    format: Format,
+    samples: u32,
+    x: u64,
+    y: u64,
+    sample: u32,
+) -> u64 {
+    let bytes_per_pixel = format.info().bytes_per_pixel as u64;
+    ((x + y * image.extent.width as u64) * samples as u64 + sample as u64) * bytes_per_pixel
+}
+
+/// Primitive in framebuffer coordinates, ready for rasterization.
+#[derive(Debug, Copy, Clone)]
+struct Primitive {
+    kind: PrimitiveKind,
+    /// Points and lines use only leading vertices.
+    vertices: [VertexShaderOutput; 3],
+    viewport_index: ViewportIndex,
+    /// Points and lines are always front-facing.
+    front_facing: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PrimitiveKind {
+    Point,
+    Line,
+    Triangle,
+}
+
+impl Primitive {
+    const fn point(vertex: VertexShaderOutput, viewport_index: ViewportIndex) -> Self {
+        Self {
+            kind: PrimitiveKind::Point,
+            vertices: [vertex; 3],
+            viewport_index,
+            front_facing: true,
+        }
+    }
+
+    const fn line(vertices: [VertexShaderOutput; 2], viewport_index: ViewportIndex) -> Self {
+        Self {
+            kind: PrimitiveKind::Line,
+            vertices: [vertices[0], vertices[1], vertices[1]],
+            viewport_index,
+            front_facing: true,
+        }
+    }
+
+    /// Conservative bounding box of pixels which may be covered by primitive,
+    /// including wide lines of polygon mode.
+    fn bounds(&self, line_width: f32) -> PixelRect {
+        let positions = match self.kind {
+            PrimitiveKind::Point => &self.vertices[..1],
+            PrimitiveKind::Line => &self.vertices[..2],
+            PrimitiveKind::Triangle => &self.vertices[..],
+        };
+        let margin = (line_width.max(1.0) / 2.0).ceil() as i64 + 1;
+        let (mut min, mut max) = ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN));
+        for vertex in positions {
+            let (x, y) = (
+                vertex.position.get_as_sfloat32(0),
+                vertex.position.get_as_sfloat32(1),
+            );
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        PixelRect {
+            min_x: min.0.floor() as i64 - margin,
+            min_y: min.1.floor() as i64 - margin,
+            max_x: max.0.floor() as i64 + margin + 1,
+            max_y: max.1.floor() as i64 + margin + 1,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct RenderArea {
+    pub extent: Extent2<u32>,
+    pub offset: Offset2<i32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderTarget {
+    pub index: RenderTargetIndex,
+    pub format: Format,
+    pub samples: u32,
+    pub image: DescriptorImage,
+}
+
+#[derive(Eq, Hash, PartialEq, Debug, Copy, Clone)]
+pub struct RenderTargetIndex(pub usize);
+
+#[derive(Debug, Clone)]
+pub struct DepthStencilTarget {
+    pub format: Format,
+    pub samples: u32,
+    pub image: DescriptorImage,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InputAssemblyState {
+    pub topology: PrimitiveTopology,
+    pub primitive_restart: bool,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TessellationState {
+    pub patch_control_points: u32,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum PrimitiveTopology {
+    #[default]
+    PointList,
+    LineList,
+    LineStrip,
+    TriangleList,
+    TriangleStrip,
+    TriangleFan,
+    LineListWithAdjacency,
+    LineStripWithAdjacency,
+    TriangleListWithAdjacency,
+    TriangleStripWithAdjacency,
+    PatchList,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ViewportState {
+    pub viewports: [Option<Viewport>; MAX_VIEWPORTS as usize],
     pub scissors: [Option<Scissor>; MAX_VIEWPORTS as usize],
 }
 
@@ -488,11 +1294,67 @@ pub struct Viewport {
     pub depth: Range2<f32>,
 }
 
+impl Viewport {
+    /// Transforms clip-space vertex into framebuffer coordinates, w component is replaced by 1/w
+    /// for perspective-correct interpolation.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#vertexpostproc-viewport
+    fn transform(&self, vertex_shader_output: &VertexShaderOutput) -> VertexShaderOutput {
+        let [x, y, z, w] = vertex_shader_output.position.get_as_f32_array();
+        // Perspective division.
+        let (x_ndc, y_ndc, z_ndc) = (x / w, y / w, z / w);
+
+        // Negative height flips the viewport vertically.
+        let (p_x, p_y, p_z) = (
+            self.extent.width,
+            self.extent.height,
+            self.depth.max - self.depth.min,
+        );
+        let (o_x, o_y, o_z) = (
+            self.offset.x + self.extent.width / 2.0,
+            self.offset.y + self.extent.height / 2.0,
+            self.depth.min,
+        );
+        let (x_screen, y_screen, z_screen) = (
+            (p_x / 2.0).mul_add(x_ndc, o_x),
+            (p_y / 2.0).mul_add(y_ndc, o_y),
+            p_z.mul_add(z_ndc, o_z),
+        );
+        VertexShaderOutput {
+            position: Position::from_sfloat32_raw(x_screen, y_screen, z_screen, 1.0 / w),
+            ..*vertex_shader_output
+        }
+    }
+
+    /// Pixels covered by viewport rectangle, which may have negative height.
+    fn pixel_rect(&self) -> PixelRect {
+        let bottom = self.offset.y + self.extent.height;
+        let (min_y, max_y) = (self.offset.y.min(bottom), self.offset.y.max(bottom));
+        PixelRect {
+            min_x: self.offset.x.floor() as i64,
+            min_y: min_y.floor() as i64,
+            max_x: (self.offset.x + self.extent.width).ceil() as i64,
+            max_y: max_y.ceil() as i64,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Default)]
 pub struct Scissor {
     pub render_area: RenderArea,
 }
 
+impl Scissor {
+    /// Scissor test of `fragment` in framebuffer coordinates.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-scissor
+    fn contains(&self, fragment: &Fragment) -> bool {
+        let x = fragment.position.get_as_sfloat32(0).floor() as i64;
+        let y = fragment.position.get_as_sfloat32(1).floor() as i64;
+        let RenderArea { extent, offset } = self.render_area;
+        (offset.x as i64..offset.x as i64 + extent.width as i64).contains(&x)
+            && (offset.y as i64..offset.y as i64 + extent.height as i64).contains(&y)
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct ViewportIndex(pub u32);
 
@@ -550,3 +1412,1365 @@ pub struct RasterizationState {
     pub depth_bias_slope_factor: f32,
     pub line_width: f32,
 }
+
+impl RasterizationState {
+    /// Determines polygon facing from sign of its area in framebuffer coordinates.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-polygons-basic
+    fn is_front_facing(&self, vertices: &[VertexShaderOutput; 3]) -> bool {
+        let area = -(0..3)
+            .map(|i| {
+                let v0 = vertices[i].position;
+                let v1 = vertices[(i + 1) % 3].position;
+                v0.get_as_sfloat32(0).mul_add(
+                    v1.get_as_sfloat32(1),
+                    -v1.get_as_sfloat32(0) * v0.get_as_sfloat32(1),
+                )
+            })
+            .sum::<f32>()
+            / 2.0;
+        match self.front_face {
+            FrontFace::CounterClockwise => area > 0.0,
+            FrontFace::Clockwise => area < 0.0,
+        }
+    }
+
+    /// Offsets depth of polygon `vertices` in framebuffer coordinates by constant and
+    /// slope-scaled depth bias. Attachments without depth aspect are not biased.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-depthbias
+    fn apply_depth_bias(&self, vertices: &mut [VertexShaderOutput; 3], depth_format: Format) {
+        if !self.depth_bias_enable || !depth_format.has_depth() {
+            return;
+        }
+        let [(x0, y0, z0), (x1, y1, z1), (x2, y2, z2)] = vertices.map(|vertex| {
+            let [x, y, z, _] = vertex.position.get_as_f32_array();
+            (x, y, z)
+        });
+
+        // Maximum depth slope.
+        let area = (x1 - x0).mul_add(y2 - y0, -(x2 - x0) * (y1 - y0));
+        let m = if area == 0.0 {
+            0.0
+        } else {
+            let dzdx = (z1 - z0).mul_add(y2 - y0, -(z2 - z0) * (y1 - y0)) / area;
+            let dzdy = (x1 - x0).mul_add(z2 - z0, -(x2 - x0) * (z1 - z0)) / area;
+            dzdx.abs().max(dzdy.abs())
+        };
+
+        // Minimum resolvable difference.
+        let r = match depth_format {
+            Format::D16Unorm => 2.0f32.powi(-16),
+            Format::X8D24UnormPack32 | Format::D24UnormS8Uint => 2.0f32.powi(-24),
+            Format::D32Sfloat | Format::D32SfloatS8Uint => {
+                // 2^(e - n), e is maximum exponent of depth values and n is number of mantissa bits.
+                const F32_MANTISSA_BITS: i32 = f32::MANTISSA_DIGITS as i32 - 1;
+                const F32_EXPONENT_MASK: u32 = 0x7f80_0000;
+                let z_max = z0.abs().max(z1.abs()).max(z2.abs());
+                let two_pow_e = if z_max < f32::MIN_POSITIVE {
+                    // Zero and subnormal depths have minimum exponent of normal numbers.
+                    f32::MIN_POSITIVE
+                } else {
+                    f32::from_bits(z_max.to_bits() & F32_EXPONENT_MASK)
+                };
+                two_pow_e * 2.0f32.powi(-F32_MANTISSA_BITS)
+            }
+            _ => unreachable!("{:?} is not a depth format", depth_format),
+        };
+
+        let bias = m.mul_add(
+            self.depth_bias_slope_factor,
+            r * self.depth_bias_constant_factor,
+        );
+        let bias = if self.depth_bias_clamp > 0.0 {
+            bias.min(self.depth_bias_clamp)
+        } else if self.depth_bias_clamp < 0.0 {
+            bias.max(self.depth_bias_clamp)
+        } else {
+            bias
+        };
+
+        for vertex in vertices {
+            let [x, y, z, w] = vertex.position.get_as_f32_array();
+            vertex.position = Position::from_sfloat32_raw(x, y, z + bias, w);
+        }
+    }
+
+    const fn is_culled(&self, front_facing: bool) -> bool {
+        match self.cull_mode {
+            CullMode::None => false,
+            CullMode::Front => front_facing,
+            CullMode::Back => !front_facing,
+            CullMode::FrontAndBack => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MultisampleState {
+    pub rasterization_samples: u32,
+    /// Bit mask of samples which may be covered.
+    pub sample_mask: u32,
+}
+
+impl Default for MultisampleState {
+    fn default() -> Self {
+        Self {
+            rasterization_samples: 1,
+            sample_mask: !0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DepthStencilState {
+    pub depth_test_enable: bool,
+    pub depth_write_enable: bool,
+    pub depth_compare_op: CompareOp,
+    pub stencil_test_enable: bool,
+    pub front: StencilOpState,
+    pub back: StencilOpState,
+    pub depth_bounds_test_enable: bool,
+    pub depth_bounds: Range2<f32>,
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StencilOpState {
+    pub fail_op: StencilOp,
+    pub pass_op: StencilOp,
+    pub depth_fail_op: StencilOp,
+    pub compare_op: CompareOp,
+    pub compare_mask: u32,
+    pub write_mask: u32,
+    pub reference: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ColorBlendState {
+    pub attachments: [Option<ColorBlendAttachmentState>; MAX_COLOR_ATTACHMENTS as usize],
+    pub blend_constants: [f32; 4],
+    pub logic_op_enable: bool,
+    pub logic_op: LogicOp,
+}
+
+#[derive(Debug, Copy, Clone)]
+pub struct ColorBlendAttachmentState {
+    pub blend_enable: bool,
+    pub src_color_blend_factor: BlendFactor,
+    pub dst_color_blend_factor: BlendFactor,
+    pub color_blend_op: BlendOp,
+    pub src_alpha_blend_factor: BlendFactor,
+    pub dst_alpha_blend_factor: BlendFactor,
+    pub alpha_blend_op: BlendOp,
+    /// Written R, G, B and A components.
+    pub color_write_mask: [bool; 4],
+}
+
+impl Default for ColorBlendAttachmentState {
+    fn default() -> Self {
+        Self {
+            blend_enable: false,
+            src_color_blend_factor: BlendFactor::One,
+            dst_color_blend_factor: BlendFactor::Zero,
+            color_blend_op: BlendOp::Add,
+            src_alpha_blend_factor: BlendFactor::One,
+            dst_alpha_blend_factor: BlendFactor::Zero,
+            alpha_blend_op: BlendOp::Add,
+            color_write_mask: [true; 4],
+        }
+    }
+}
+
+/// Pipeline states specified by dynamic state commands instead of pipeline.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct DynamicStates {
+    pub viewport: bool,
+    pub scissor: bool,
+    pub line_width: bool,
+    pub depth_bias: bool,
+    pub blend_constants: bool,
+    pub depth_bounds: bool,
+    pub stencil_compare_mask: bool,
+    pub stencil_write_mask: bool,
+    pub stencil_reference: bool,
+}
+
+/// Stencil faces updated by stencil dynamic state commands.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct StencilFaces {
+    pub front: bool,
+    pub back: bool,
+}
+
+/// Values set by dynamic state commands, persisting across pipeline binds.
+#[derive(Debug, Clone, Default)]
+struct DynamicStateValues {
+    viewports: [Option<Viewport>; MAX_VIEWPORTS as usize],
+    scissors: [Option<Scissor>; MAX_VIEWPORTS as usize],
+    line_width: Option<f32>,
+    depth_bias: Option<(f32, f32, f32)>,
+    blend_constants: Option<[f32; 4]>,
+    depth_bounds: Option<Range2<f32>>,
+    stencil_front: StencilDynamicStateValues,
+    stencil_back: StencilDynamicStateValues,
+}
+
+impl DynamicStateValues {
+    fn stencil_faces_mut(
+        &mut self,
+        faces: StencilFaces,
+    ) -> impl Iterator<Item = &mut StencilDynamicStateValues> {
+        [
+            (faces.front, &mut self.stencil_front),
+            (faces.back, &mut self.stencil_back),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, values)| enabled.then_some(values))
+    }
+}
+
+#[derive(Debug, Copy, Clone, Default)]
+struct StencilDynamicStateValues {
+    compare_mask: Option<u32>,
+    write_mask: Option<u32>,
+    reference: Option<u32>,
+}
+
+#[cfg(test)]
+mod test_harness;
+
+#[cfg(test)]
+mod tests {
+    use super::test_harness::*;
+    use super::*;
+    use crate::{MemoryHandleStore, TILE_SIZE};
+    use common::consts::MAX_VERTEX_ATTRIBUTES;
+    use common::graphics::{MemoryBinding, VertexAttribute, VertexBinding, VertexBindingNumber};
+
+    fn depth_less() -> DepthStencilState {
+        DepthStencilState {
+            depth_test_enable: true,
+            depth_write_enable: true,
+            depth_compare_op: CompareOp::Less,
+            ..Default::default()
+        }
+    }
+
+    /// Depth and stencil tests always passing and replacing stored values.
+    fn depth_stencil_replace() -> DepthStencilState {
+        let replace = StencilOpState {
+            pass_op: StencilOp::Replace,
+            compare_op: CompareOp::Always,
+            compare_mask: !0,
+            write_mask: !0,
+            reference: 1,
+            ..Default::default()
+        };
+        DepthStencilState {
+            depth_test_enable: true,
+            depth_write_enable: true,
+            depth_compare_op: CompareOp::Always,
+            stencil_test_enable: true,
+            front: replace,
+            back: replace,
+            ..Default::default()
+        }
+    }
+
+    /// Triangle in framebuffer coordinates, counter-clockwise if `counter_clockwise`.
+    fn wound_triangle(counter_clockwise: bool) -> [VertexShaderOutput; 3] {
+        let corners = if counter_clockwise {
+            [(0.0, 0.0), (0.0, 4.0), (4.0, 0.0)]
+        } else {
+            [(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)]
+        };
+        corners.map(|(x, y)| VertexShaderOutput {
+            position: Position::from_sfloat32_raw(x, y, 0.5, 1.0),
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn cull_mode_and_front_face_select_culled_windings() {
+        use CullMode::{Back, Front, FrontAndBack};
+        use FrontFace::{Clockwise, CounterClockwise};
+        // (front_face, cull_mode, counter_clockwise, culled)
+        let expected = [
+            (CounterClockwise, CullMode::None, true, false),
+            (CounterClockwise, CullMode::None, false, false),
+            (CounterClockwise, Front, true, true),
+            (CounterClockwise, Front, false, false),
+            (CounterClockwise, Back, true, false),
+            (CounterClockwise, Back, false, true),
+            (CounterClockwise, FrontAndBack, true, true),
+            (CounterClockwise, FrontAndBack, false, true),
+            (Clockwise, CullMode::None, true, false),
+            (Clockwise, CullMode::None, false, false),
+            (Clockwise, Front, true, false),
+            (Clockwise, Front, false, true),
+            (Clockwise, Back, true, true),
+            (Clockwise, Back, false, false),
+            (Clockwise, FrontAndBack, true, true),
+            (Clockwise, FrontAndBack, false, true),
+        ];
+        for (front_face, cull_mode, counter_clockwise, culled) in expected {
+            let rasterization_state = RasterizationState {
+                front_face,
+                cull_mode,
+                ..Default::default()
+            };
+            let front_facing =
+                rasterization_state.is_front_facing(&wound_triangle(counter_clockwise));
+            assert_eq!(
+                rasterization_state.is_culled(front_facing),
+                culled,
+                "{front_face:?} {cull_mode:?} {counter_clockwise}"
+            );
+        }
+    }
+
+    #[test]
+    fn patch_list_is_not_drawn_without_tessellation() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer
+            .pipeline
+            .set_input_assembly_state(InputAssemblyState {
+                topology: PrimitiveTopology::PatchList,
+                primitive_restart: false,
+            });
+        framebuffer
+            .pipeline
+            .set_tessellation_state(TessellationState {
+                patch_control_points: 3,
+            });
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), vec![]);
+    }
+
+    #[test]
+    fn viewport_with_negative_height_flips_facing() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer
+            .pipeline
+            .set_rasterization_state(RasterizationState {
+                cull_mode: CullMode::Back,
+                front_face: FrontFace::CounterClockwise,
+                ..Default::default()
+            });
+        // Full screen triangle is clockwise in framebuffer coordinates.
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), vec![]);
+
+        let mut flipped = viewport(rect(0, 8, 8, 8));
+        flipped.extent.height = -8.0;
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(flipped);
+        framebuffer.pipeline.set_viewport_state(viewport_state);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(0, 0, 8, 8)));
+    }
+
+    #[test]
+    fn fragment_shader_reads_front_facing() {
+        let fragment_shader = FragmentShader {
+            front_facing: true,
+            ..Default::default()
+        };
+        let mut framebuffer = Framebuffer::new(fragment_shader);
+        let corners = [(-1.0, -1.0), (3.0, -1.0), (-1.0, 3.0)];
+        framebuffer.draw(&triangle_vertices(corners, 0.5, RED));
+        assert_eq!(framebuffer.color(0, 4, 4), [0, 0, 255, 255]);
+
+        let [v0, v1, v2] = corners;
+        framebuffer.draw(&triangle_vertices([v0, v2, v1], 0.5, RED));
+        assert_eq!(framebuffer.color(0, 4, 4), [255, 0, 0, 255]);
+    }
+
+    fn triangle(z: f32) -> [VertexShaderOutput; 3] {
+        [(0.0, 0.0), (4.0, 0.0), (0.0, 4.0)].map(|(x, y)| VertexShaderOutput {
+            position: Position::from_sfloat32_raw(x, y, z, 1.0),
+            ..Default::default()
+        })
+    }
+
+    fn biased_depths(format: Format, mut vertices: [VertexShaderOutput; 3]) -> [f32; 3] {
+        let rasterization_state = RasterizationState {
+            depth_bias_enable: true,
+            depth_bias_constant_factor: 2.0,
+            ..Default::default()
+        };
+        rasterization_state.apply_depth_bias(&mut vertices, format);
+        vertices.map(|vertex| vertex.position.get_as_sfloat32(2))
+    }
+
+    fn biased_depth(format: Format) -> f32 {
+        biased_depths(format, triangle(0.5))[0]
+    }
+
+    #[test]
+    fn depth_bias_of_combined_depth_stencil_formats() {
+        assert_eq!(
+            biased_depth(Format::D24UnormS8Uint),
+            biased_depth(Format::X8D24UnormPack32)
+        );
+        assert_eq!(
+            biased_depth(Format::D24UnormS8Uint),
+            2.0f32.mul_add(2.0f32.powi(-24), 0.5)
+        );
+        assert_eq!(
+            biased_depth(Format::D32SfloatS8Uint),
+            biased_depth(Format::D32Sfloat)
+        );
+        assert_eq!(
+            biased_depth(Format::D32SfloatS8Uint),
+            2.0f32.mul_add(2.0f32.powi(-24), 0.5)
+        );
+        assert_eq!(biased_depth(Format::S8Uint), 0.5);
+    }
+
+    #[test]
+    fn d32_depth_bias_uses_exponent_of_largest_depth_of_primitive() {
+        let r = |e: i32| 2.0f32.powi(e) * 2.0f32.powi(-23);
+        assert_eq!(
+            biased_depths(Format::D32Sfloat, triangle(0.75)),
+            [2.0f32.mul_add(r(-1), 0.75); 3]
+        );
+        assert_eq!(
+            biased_depths(Format::D32Sfloat, triangle(1.0)),
+            [2.0f32.mul_add(r(0), 1.0); 3]
+        );
+
+        let mut vertices = triangle(0.25);
+        vertices[1].position = Position::from_sfloat32_raw(4.0, 0.0, -3.0, 1.0);
+        assert_eq!(
+            biased_depths(Format::D32Sfloat, vertices),
+            [0.25, -3.0, 0.25].map(|z| 2.0f32.mul_add(r(1), z))
+        );
+
+        // Zero depth is biased by multiples of smallest subnormal, not by 2^-23.
+        assert_eq!(
+            biased_depths(Format::D32Sfloat, triangle(0.0)),
+            [2.0 * f32::from_bits(1); 3]
+        );
+    }
+
+    #[test]
+    fn indirect_command_outside_of_buffer_is_zero() {
+        let mut memory = Memory::new();
+        let mut binding = MemoryBinding::new();
+        binding.store(memory.allocate_memory(64), 0, 24);
+        let buffer = DescriptorBuffer { binding };
+        let values = [3u32, 1, 4, 1, 5, 9];
+        let bytes = values
+            .iter()
+            .flat_map(|value| value.to_ne_bytes())
+            .collect::<Vec<_>>();
+        memory.write_bytes(&bytes, &buffer.binding, 0);
+
+        assert_eq!(read_indirect_command(&memory, &buffer, 8), [4, 1, 5, 9]);
+        assert_eq!(read_indirect_command::<4>(&memory, &buffer, 12), [0; 4]);
+        assert_eq!(
+            read_indirect_command::<4>(&memory, &buffer, u64::MAX),
+            [0; 4]
+        );
+    }
+
+    /// Binds red triangle covering pixel (1, 1) followed by blue triangle covering pixel (6, 6).
+    fn bind_two_triangles(framebuffer: &mut Framebuffer) {
+        let vertices = [
+            triangle_vertices([(-1.0, -1.0), (0.0, -1.0), (-1.0, 0.0)], 0.5, RED),
+            triangle_vertices([(1.0, 1.0), (0.0, 1.0), (1.0, 0.0)], 0.5, BLUE),
+        ]
+        .concat();
+        framebuffer.bind_vertices(&vertices);
+    }
+
+    #[test]
+    fn indirect_draws_read_records_at_stride() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        bind_two_triangles(&mut framebuffer);
+        // Records of 4 values padded to stride of 6 values, starting at offset of 1 value.
+        let records = [0, 3, 1, 0, 0, 0, 0, 3, 1, 3, 0, 0, 0];
+        let buffer = u32_buffer(&mut framebuffer.memory, records);
+        framebuffer
+            .pipeline
+            .draw_primitive_indirect(&mut framebuffer.memory, buffer, 4, 2, 24);
+        assert_eq!(framebuffer.color(0, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.color(0, 6, 6), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn indexed_indirect_draws_read_records_at_stride() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        bind_two_triangles(&mut framebuffer);
+        let indices = u32_buffer(&mut framebuffer.memory, [0, 1, 2]);
+        framebuffer.pipeline.bind_index_buffer(IndexBuffer {
+            buffer: indices,
+            offset: 0,
+            index_size: 4,
+        });
+        // Records of 5 values padded to stride of 8 values, second one draws the same indices
+        // with vertex offset 3.
+        let records = [3, 1, 0, 0, 0, 0, 0, 0, 3, 1, 0, 3, 0, 0, 0, 0];
+        let buffer = u32_buffer(&mut framebuffer.memory, records);
+        framebuffer.pipeline.draw_primitive_indexed_indirect(
+            &mut framebuffer.memory,
+            buffer,
+            0,
+            2,
+            32,
+        );
+        assert_eq!(framebuffer.color(0, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.color(0, 6, 6), [0, 0, 255, 255]);
+    }
+
+    #[test]
+    fn depth_compare_op_tests_fragment_depth_against_stored_depth() {
+        for (compare_op, z, passes) in [
+            (CompareOp::Never, 0.25, false),
+            (CompareOp::Less, 0.25, true),
+            (CompareOp::Less, 0.5, false),
+            (CompareOp::Equal, 0.5, true),
+            (CompareOp::Equal, 0.75, false),
+            (CompareOp::LessOrEqual, 0.5, true),
+            (CompareOp::Greater, 0.75, true),
+            (CompareOp::Greater, 0.25, false),
+            (CompareOp::NotEqual, 0.5, false),
+            (CompareOp::GreaterOrEqual, 0.5, true),
+            (CompareOp::Always, 0.75, true),
+        ] {
+            let mut framebuffer = Framebuffer::new(color_fragment_shader());
+            framebuffer
+                .pipeline
+                .set_depth_stencil_state(DepthStencilState {
+                    depth_compare_op: compare_op,
+                    ..depth_less()
+                });
+            framebuffer.clear_depth(0.5);
+            framebuffer.draw_full_screen(z);
+            let (color, depth) = if passes {
+                ([255, 0, 0, 255], z)
+            } else {
+                ([0; 4], 0.5)
+            };
+            assert_eq!(framebuffer.color(0, 4, 4), color, "{compare_op:?} {z}");
+            assert_eq!(framebuffer.depth_stencil(4, 4), (depth, 0));
+        }
+    }
+
+    #[test]
+    fn depth_is_written_only_if_test_and_writes_are_enabled() {
+        for (depth_test_enable, depth_write_enable, depth) in
+            [(true, true, 0.25), (true, false, 0.5), (false, true, 0.5)]
+        {
+            let mut framebuffer = Framebuffer::new(color_fragment_shader());
+            framebuffer
+                .pipeline
+                .set_depth_stencil_state(DepthStencilState {
+                    depth_test_enable,
+                    depth_write_enable,
+                    ..depth_less()
+                });
+            framebuffer.clear_depth(0.5);
+            framebuffer.draw_full_screen(0.25);
+            assert_eq!(framebuffer.color(0, 4, 4), [255, 0, 0, 255]);
+            assert_eq!(framebuffer.depth_stencil(4, 4), (depth, 0));
+        }
+    }
+
+    /// Stencil test of both faces with `state`, depth test and writes are disabled.
+    fn stencil(state: StencilOpState) -> DepthStencilState {
+        DepthStencilState {
+            stencil_test_enable: true,
+            front: state,
+            back: state,
+            ..Default::default()
+        }
+    }
+
+    /// Draws full screen triangle at depth `z` over depth 0.5 and `stored` stencil, returns
+    /// resulting stencil value.
+    fn draw_stencil(depth_stencil_state: DepthStencilState, stored: u32, z: f32) -> u8 {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer
+            .pipeline
+            .set_depth_stencil_state(depth_stencil_state);
+        framebuffer.clear_depth(0.5);
+        framebuffer.clear_stencil(stored);
+        framebuffer.draw_full_screen(z);
+        framebuffer.depth_stencil(4, 4).1
+    }
+
+    #[test]
+    fn stencil_compare_op_tests_masked_reference_against_masked_stored_value() {
+        // Masked reference is 5, unmasked comparisons would give opposite results for 0xa4 and
+        // 0x16.
+        for (compare_op, passes) in [
+            (CompareOp::Never, [false, false, false]),
+            (CompareOp::Less, [false, false, true]),
+            (CompareOp::Equal, [false, true, false]),
+            (CompareOp::LessOrEqual, [false, true, true]),
+            (CompareOp::Greater, [true, false, false]),
+            (CompareOp::NotEqual, [true, false, true]),
+            (CompareOp::GreaterOrEqual, [true, true, false]),
+            (CompareOp::Always, [true, true, true]),
+        ] {
+            for (stored, passes) in [0xa4, 0x05, 0x16].into_iter().zip(passes) {
+                let state = StencilOpState {
+                    pass_op: StencilOp::Zero,
+                    compare_op,
+                    compare_mask: 0x0f,
+                    write_mask: !0,
+                    reference: 0x35,
+                    ..Default::default()
+                };
+                let expected = if passes { 0 } else { stored as u8 };
+                assert_eq!(
+                    draw_stencil(stencil(state), stored, 0.5),
+                    expected,
+                    "{compare_op:?} {stored:#x}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn stencil_compare_mask_selects_compared_bits() {
+        for (compare_mask, passes) in [(0x0f, true), (0xff, false)] {
+            let state = StencilOpState {
+                pass_op: StencilOp::Zero,
+                compare_op: CompareOp::Equal,
+                compare_mask,
+                write_mask: !0,
+                reference: 0x12,
+                ..Default::default()
+            };
+            let expected = if passes { 0 } else { 0x32 };
+            assert_eq!(
+                draw_stencil(stencil(state), 0x32, 0.5),
+                expected,
+                "{compare_mask:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn stencil_op_depends_on_stencil_and_depth_test_results() {
+        let state = StencilOpState {
+            fail_op: StencilOp::Invert,
+            depth_fail_op: StencilOp::IncrementAndClamp,
+            pass_op: StencilOp::Replace,
+            compare_op: CompareOp::Always,
+            compare_mask: !0,
+            write_mask: !0,
+            reference: 0x80,
+        };
+        let depth_stencil_state = |compare_op| DepthStencilState {
+            stencil_test_enable: true,
+            front: StencilOpState {
+                compare_op,
+                ..state
+            },
+            back: StencilOpState {
+                compare_op,
+                ..state
+            },
+            ..depth_less()
+        };
+        // Failing stencil test skips depth test.
+        assert_eq!(
+            draw_stencil(depth_stencil_state(CompareOp::Never), 0x11, 0.25),
+            0xee
+        );
+        assert_eq!(
+            draw_stencil(depth_stencil_state(CompareOp::Always), 0x11, 0.75),
+            0x12
+        );
+        assert_eq!(
+            draw_stencil(depth_stencil_state(CompareOp::Always), 0x11, 0.25),
+            0x80
+        );
+    }
+
+    #[test]
+    fn stencil_increment_and_decrement_clamp_or_wrap() {
+        for (pass_op, stored, expected) in [
+            (StencilOp::IncrementAndClamp, 0xff, 0xff),
+            (StencilOp::IncrementAndWrap, 0xff, 0x00),
+            (StencilOp::IncrementAndClamp, 0x41, 0x42),
+            (StencilOp::DecrementAndClamp, 0x00, 0x00),
+            (StencilOp::DecrementAndWrap, 0x00, 0xff),
+            (StencilOp::DecrementAndWrap, 0x41, 0x40),
+        ] {
+            let state = StencilOpState {
+                pass_op,
+                compare_op: CompareOp::Always,
+                write_mask: !0,
+                ..Default::default()
+            };
+            assert_eq!(
+                draw_stencil(stencil(state), stored, 0.5),
+                expected,
+                "{pass_op:?} {stored:#x}"
+            );
+        }
+    }
+
+    #[test]
+    fn stencil_write_mask_selects_written_bits() {
+        let state = StencilOpState {
+            pass_op: StencilOp::Replace,
+            compare_op: CompareOp::Always,
+            write_mask: 0x0f,
+            reference: 0b0101_0101,
+            ..Default::default()
+        };
+        assert_eq!(draw_stencil(stencil(state), 0b1010_1010, 0.5), 0b1010_0101);
+    }
+
+    #[test]
+    fn back_facing_triangles_use_back_stencil_state() {
+        let replace = |reference| StencilOpState {
+            pass_op: StencilOp::Replace,
+            compare_op: CompareOp::Always,
+            write_mask: !0,
+            reference,
+            ..Default::default()
+        };
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer
+            .pipeline
+            .set_depth_stencil_state(DepthStencilState {
+                stencil_test_enable: true,
+                front: replace(1),
+                back: replace(2),
+                ..Default::default()
+            });
+        // Full screen triangle is clockwise in framebuffer coordinates, so it is back-facing.
+        let corners = [(-1.0, -1.0), (3.0, -1.0), (-1.0, 3.0)];
+        framebuffer.draw(&triangle_vertices(corners, 0.5, RED));
+        assert_eq!(framebuffer.depth_stencil(4, 4), (1.0, 2));
+
+        let [v0, v1, v2] = corners;
+        framebuffer.draw(&triangle_vertices([v0, v2, v1], 0.5, RED));
+        assert_eq!(framebuffer.depth_stencil(4, 4), (1.0, 1));
+    }
+
+    #[test]
+    fn frag_depth_gets_late_tests() {
+        let fragment_shader = FragmentShader {
+            frag_depth: Some(0.25),
+            ..color_fragment_shader()
+        };
+        assert!(!fragment_shader.build().early_fragment_tests());
+        let mut framebuffer = Framebuffer::new(fragment_shader);
+        framebuffer.pipeline.set_depth_stencil_state(depth_less());
+        framebuffer.clear_depth(0.5);
+        // Interpolated depth 0.75 would fail the test, written depth 0.25 passes it.
+        framebuffer.draw_full_screen(0.75);
+        assert_eq!(framebuffer.color(0, 4, 4), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.depth_stencil(4, 4), (0.25, 0));
+    }
+
+    #[test]
+    fn discard_gets_late_tests_and_does_not_update_depth_stencil() {
+        let fragment_shader = FragmentShader {
+            discard: true,
+            ..color_fragment_shader()
+        };
+        assert!(!fragment_shader.build().early_fragment_tests());
+        let mut framebuffer = Framebuffer::new(fragment_shader);
+        framebuffer
+            .pipeline
+            .set_depth_stencil_state(depth_stencil_replace());
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.color(0, 4, 4), [0; 4]);
+        assert_eq!(framebuffer.depth_stencil(4, 4), (1.0, 0));
+    }
+
+    #[test]
+    fn early_fragment_tests_mode_forces_early_tests() {
+        // Test uses interpolated depth 0.75 instead of written 0.25, so it fails.
+        let fragment_shader = FragmentShader {
+            frag_depth: Some(0.25),
+            early_fragment_tests: true,
+            ..color_fragment_shader()
+        };
+        assert!(fragment_shader.build().early_fragment_tests());
+        let mut framebuffer = Framebuffer::new(fragment_shader);
+        framebuffer.pipeline.set_depth_stencil_state(depth_less());
+        framebuffer.clear_depth(0.5);
+        framebuffer.draw_full_screen(0.75);
+        assert_eq!(framebuffer.color(0, 4, 4), [0; 4]);
+        assert_eq!(framebuffer.depth_stencil(4, 4), (0.5, 0));
+    }
+
+    #[test]
+    fn early_fragment_tests_update_depth_stencil_of_discarded_fragments() {
+        let fragment_shader = FragmentShader {
+            discard: true,
+            early_fragment_tests: true,
+            ..color_fragment_shader()
+        };
+        let mut framebuffer = Framebuffer::new(fragment_shader);
+        framebuffer
+            .pipeline
+            .set_depth_stencil_state(depth_stencil_replace());
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.color(0, 4, 4), [0; 4]);
+        assert_eq!(framebuffer.depth_stencil(4, 4), (0.5, 1));
+    }
+
+    #[test]
+    fn shader_without_depth_writes_and_discard_gets_early_tests() {
+        assert!(color_fragment_shader().build().early_fragment_tests());
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer.pipeline.set_depth_stencil_state(depth_less());
+        framebuffer.clear_depth(0.5);
+        framebuffer.draw_full_screen(0.25);
+        assert_eq!(framebuffer.color(0, 4, 4), [255, 0, 0, 255]);
+        assert_eq!(framebuffer.depth_stencil(4, 4), (0.25, 0));
+    }
+
+    #[test]
+    fn outputs_at_non_contiguous_locations_write_attachments_at_same_indices() {
+        let fragment_shader = FragmentShader {
+            constant_outputs: vec![(0, [0.0, 0.0, 1.0, 1.0]), (3, [0.0, 1.0, 0.0, 1.0])],
+            ..Default::default()
+        };
+        let attachments = [0, 1, 3].map(|index| (index, COLOR_FORMAT));
+        let mut framebuffer = Framebuffer::with_attachments(EXTENT, &attachments, fragment_shader);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.color(0, 4, 4), [0, 0, 255, 255]);
+        assert_eq!(framebuffer.color(1, 4, 4), [0; 4]);
+        assert_eq!(framebuffer.color(3, 4, 4), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn output_without_bound_attachment_is_discarded() {
+        let fragment_shader = FragmentShader {
+            constant_outputs: vec![(1, [0.0, 0.0, 1.0, 1.0]), (2, [0.0, 1.0, 0.0, 1.0])],
+            ..Default::default()
+        };
+        let mut framebuffer =
+            Framebuffer::with_attachments(EXTENT, &[(2, COLOR_FORMAT)], fragment_shader);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.color(2, 4, 4), [0, 255, 0, 255]);
+    }
+
+    #[test]
+    fn integer_attachment_is_not_blended_and_gets_logic_op_on_raw_bits() {
+        let fragment_shader = FragmentShader {
+            uint_outputs: vec![(0, [1, 2, 0x80, 0x1ff])],
+            ..Default::default()
+        };
+        let mut framebuffer =
+            Framebuffer::with_attachments(EXTENT, &[(0, Format::R8G8B8A8Uint)], fragment_shader);
+        let mut color_blend_state = ColorBlendState::default();
+        color_blend_state.attachments[0] = Some(ColorBlendAttachmentState {
+            blend_enable: true,
+            dst_color_blend_factor: BlendFactor::One,
+            dst_alpha_blend_factor: BlendFactor::One,
+            ..Default::default()
+        });
+        framebuffer
+            .pipeline
+            .set_color_blend_state(color_blend_state.clone());
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.color(0, 4, 4), [1, 2, 0x80, 0xff]);
+
+        color_blend_state.logic_op_enable = true;
+        color_blend_state.logic_op = LogicOp::Invert;
+        framebuffer
+            .pipeline
+            .set_color_blend_state(color_blend_state);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.color(0, 4, 4), [0xfe, 0xfd, 0x7f, 0x00]);
+    }
+
+    /// Pipeline fetching attribute at `Location` N from binding N for each
+    /// `(format, stride, input_rate, values)`, binding buffer holds `values` as floats.
+    fn fetch_pipeline(
+        memory: &mut Memory,
+        bindings: &[(Format, u32, VertexInputRate, Vec<f32>)],
+    ) -> GraphicsPipeline {
+        let mut pipeline = GraphicsPipeline::new();
+        let mut vertex_input_state = VertexInputState {
+            attributes: [None; MAX_VERTEX_ATTRIBUTES as usize],
+            bindings: [None; MAX_VERTEX_BINDINGS as usize],
+        };
+        for (number, (format, stride, input_rate, values)) in bindings.iter().enumerate() {
+            vertex_input_state.attributes[number] = Some(VertexAttribute {
+                location: number as u32,
+                binding: VertexBindingNumber(number as u32),
+                format: *format,
+                offset: 0,
+            });
+            vertex_input_state.bindings[number] = Some(VertexBinding {
+                number: VertexBindingNumber(number as u32),
+                stride: *stride,
+                input_rate: *input_rate,
+            });
+            pipeline.bind_vertex_buffer(VertexBuffer {
+                binding_number: VertexBindingNumber(number as u32),
+                buffer: float_buffer(memory, values.iter().copied()),
+                offset: 0,
+            });
+        }
+        pipeline.set_vertex_input_state(vertex_input_state);
+        pipeline
+    }
+
+    #[test]
+    fn fetch_instances_with_per_instance_attributes() {
+        let mut memory = Memory::new();
+        let elements = |number: usize| {
+            (0..8)
+                .flat_map(|element| [(100 * number + element) as f32, 0.0, 0.0, 1.0])
+                .collect::<Vec<_>>()
+        };
+        let format = Format::R32G32B32A32Sfloat;
+        let pipeline = fetch_pipeline(
+            &mut memory,
+            &[
+                (format, 16, VertexInputRate::Vertex, elements(0)),
+                (format, 16, VertexInputRate::Instance, elements(1)),
+            ],
+        );
+
+        // 2 vertices from `firstVertex` 1 of 3 instances from `firstInstance` 4.
+        let (vertices, strips) = pipeline.fetch_vertex_input(&memory, 2, 3, 1, 4);
+        assert_eq!(strips, vec![0..2, 2..4, 4..6]);
+        assert_eq!(
+            vertices
+                .iter()
+                .map(|vertex| (
+                    vertex.index,
+                    vertex.instance_index,
+                    vertex.attributes[0].get_as_sfloat32(0),
+                    vertex.attributes[1].get_as_sfloat32(0),
+                ))
+                .collect::<Vec<_>>(),
+            vec![
+                (1, 4, 1.0, 104.0),
+                (2, 4, 2.0, 104.0),
+                (1, 5, 1.0, 105.0),
+                (2, 5, 2.0, 105.0),
+                (1, 6, 1.0, 106.0),
+                (2, 6, 2.0, 106.0),
+            ]
+        );
+    }
+
+    #[test]
+    fn vertex_and_instance_indices_wrap_around() {
+        let mut memory = Memory::new();
+        let format = Format::R32G32B32A32Sfloat;
+        let pipeline = fetch_pipeline(
+            &mut memory,
+            &[(
+                format,
+                16,
+                VertexInputRate::Vertex,
+                vec![1.0, 0.0, 0.0, 1.0],
+            )],
+        );
+        let (vertices, strips) = pipeline.fetch_vertex_input(&memory, 2, 2, u32::MAX, u32::MAX);
+        assert_eq!(strips, vec![0..2, 2..4]);
+        assert_eq!(
+            vertices
+                .iter()
+                .map(|vertex| (vertex.index, vertex.instance_index))
+                .collect::<Vec<_>>(),
+            vec![(u32::MAX, u32::MAX), (0, u32::MAX), (u32::MAX, 0), (0, 0)]
+        );
+        assert_eq!(vertices[1].attributes[0].get_as_sfloat32(0), 1.0);
+    }
+
+    /// Pipeline fetching 8 vertices with attribute `(index, 0, 0, 1)` indexed by `indices` of
+    /// `index_size` bytes.
+    fn indexed_fetch_pipeline(
+        memory: &mut Memory,
+        index_size: u8,
+        indices: &[u32],
+        primitive_restart: bool,
+    ) -> GraphicsPipeline {
+        let elements = (0..8)
+            .flat_map(|element| [element as f32, 0.0, 0.0, 1.0])
+            .collect();
+        let mut pipeline = fetch_pipeline(
+            memory,
+            &[(
+                Format::R32G32B32A32Sfloat,
+                16,
+                VertexInputRate::Vertex,
+                elements,
+            )],
+        );
+        pipeline.set_input_assembly_state(InputAssemblyState {
+            topology: PrimitiveTopology::TriangleStrip,
+            primitive_restart,
+        });
+        let bytes = indices
+            .iter()
+            .flat_map(|index| index.to_ne_bytes()[..index_size as usize].to_vec())
+            .collect::<Vec<_>>();
+        let mut binding = MemoryBinding::new();
+        binding.store(
+            memory.allocate_memory(bytes.len() as u64),
+            0,
+            bytes.len() as u64,
+        );
+        memory.write_bytes(&bytes, &binding, 0);
+        pipeline.bind_index_buffer(IndexBuffer {
+            buffer: DescriptorBuffer { binding },
+            offset: 0,
+            index_size,
+        });
+        pipeline
+    }
+
+    /// Returns vertex index and first attribute component of `vertices`.
+    fn fetched(vertices: &[Vertex]) -> Vec<(u32, f32)> {
+        vertices
+            .iter()
+            .map(|vertex| (vertex.index, vertex.attributes[0].get_as_sfloat32(0)))
+            .collect()
+    }
+
+    #[test]
+    fn indexed_draw_starts_at_first_index_and_adds_vertex_offset() {
+        let mut memory = Memory::new();
+        let pipeline = indexed_fetch_pipeline(&mut memory, 4, &[7, 0, 1, 2, 6], false);
+        let (vertices, _) = pipeline.fetch_vertex_input_indexed(&memory, 3, 1, 1, 3, 0);
+        assert_eq!(fetched(&vertices), vec![(3, 3.0), (4, 4.0), (5, 5.0)]);
+
+        let (vertices, _) = pipeline.fetch_vertex_input_indexed(&memory, 2, 1, 3, -2, 0);
+        assert_eq!(fetched(&vertices), vec![(0, 0.0), (4, 4.0)]);
+    }
+
+    #[test]
+    fn indexed_draw_reads_data_past_end_of_buffers_as_zero() {
+        let mut memory = Memory::new();
+        let pipeline = indexed_fetch_pipeline(&mut memory, 2, &[5, 6, 7, 8], false);
+        // Index 8 is past vertex buffer, 2 indices are past index buffer.
+        let (vertices, _) = pipeline.fetch_vertex_input_indexed(&memory, 5, 1, 1, 0, 0);
+        assert_eq!(
+            fetched(&vertices),
+            vec![(6, 6.0), (7, 7.0), (8, 0.0), (0, 0.0), (0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn indexed_draw_from_largest_first_index_and_instance_does_not_overflow() {
+        let mut memory = Memory::new();
+        let pipeline = indexed_fetch_pipeline(&mut memory, 4, &[3, 4], false);
+        let (vertices, strips) =
+            pipeline.fetch_vertex_input_indexed(&memory, 2, 2, u32::MAX, 0, u32::MAX);
+        assert_eq!(strips, vec![0..2, 2..4]);
+        // Indices past index buffer are read as zero.
+        assert_eq!(fetched(&vertices), vec![(0, 0.0); 4]);
+        assert_eq!(
+            vertices
+                .iter()
+                .map(|vertex| vertex.instance_index)
+                .collect::<Vec<_>>(),
+            vec![u32::MAX, u32::MAX, 0, 0]
+        );
+    }
+
+    #[test]
+    fn primitive_restart_index_splits_strips() {
+        let mut memory = Memory::new();
+        for (index_size, restart_index) in [(2, 0xffff), (4, u32::MAX)] {
+            let indices = [0, 1, 2, restart_index, 3, 4, 5, 6, restart_index];
+            let pipeline = indexed_fetch_pipeline(&mut memory, index_size, &indices, true);
+            let (vertices, strips) =
+                pipeline.fetch_vertex_input_indexed(&memory, indices.len() as u32, 1, 0, 0, 0);
+            assert_eq!(strips, vec![0..3, 3..7, 7..7]);
+            assert_eq!(
+                vertices
+                    .iter()
+                    .map(|vertex| vertex.index)
+                    .collect::<Vec<_>>(),
+                vec![0, 1, 2, 3, 4, 5, 6]
+            );
+        }
+    }
+
+    #[test]
+    fn restart_index_is_ordinary_index_without_primitive_restart() {
+        let mut memory = Memory::new();
+        let indices = [0, 1, 0xffff, 2];
+        let pipeline = indexed_fetch_pipeline(&mut memory, 2, &indices, false);
+        let (vertices, strips) = pipeline.fetch_vertex_input_indexed(&memory, 4, 1, 0, 0, 0);
+        assert_eq!(strips, vec![0..4]);
+        assert_eq!(
+            vertices
+                .iter()
+                .map(|vertex| vertex.index)
+                .collect::<Vec<_>>(),
+            vec![0, 1, 0xffff, 2]
+        );
+    }
+
+    #[test]
+    fn zero_stride_reads_same_element_for_all_vertices_and_instances() {
+        let mut memory = Memory::new();
+        let format = Format::R32G32B32A32Sfloat;
+        let values = vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0, 8.0];
+        let pipeline = fetch_pipeline(
+            &mut memory,
+            &[
+                (format, 0, VertexInputRate::Vertex, values.clone()),
+                (format, 0, VertexInputRate::Instance, values),
+            ],
+        );
+        let (vertices, _) = pipeline.fetch_vertex_input(&memory, 3, 2, 0, 0);
+        for vertex in vertices {
+            assert_eq!(
+                vertex.attributes[0].get_as_f32_array(),
+                [1.0, 2.0, 3.0, 4.0]
+            );
+            assert_eq!(
+                vertex.attributes[1].get_as_f32_array(),
+                [1.0, 2.0, 3.0, 4.0]
+            );
+        }
+    }
+
+    #[test]
+    fn missing_attribute_components_are_expanded_to_0_0_0_1() {
+        let mut memory = Memory::new();
+        let pipeline = fetch_pipeline(
+            &mut memory,
+            &[
+                (
+                    Format::R32Sfloat,
+                    4,
+                    VertexInputRate::Vertex,
+                    vec![1.0, 2.0],
+                ),
+                (
+                    Format::R32G32B32Sfloat,
+                    12,
+                    VertexInputRate::Vertex,
+                    vec![1.0, 2.0, 3.0, 4.0, 5.0, 6.0],
+                ),
+            ],
+        );
+        let vertex = pipeline.fetch_vertex(&memory, 1, 0);
+        assert_eq!(
+            vertex.attributes[0].get_as_f32_array(),
+            [2.0, 0.0, 0.0, 1.0]
+        );
+        assert_eq!(
+            vertex.attributes[1].get_as_f32_array(),
+            [4.0, 5.0, 6.0, 1.0]
+        );
+    }
+
+    #[test]
+    fn vec3_position_attribute_gets_w_of_one() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        let Some(position) = framebuffer.pipeline.vertex_input_state.attributes[0].as_mut() else {
+            unreachable!()
+        };
+        position.format = Format::R32G32B32Sfloat;
+        // W stored after position in vertex buffer is not part of attribute.
+        let vertices = triangle_vertices([(-1.0, -1.0), (3.0, -1.0), (-1.0, 3.0)], 0.5, RED)
+            .map(|[[x, y, z, _], color]| [[x, y, z, 0.0], color]);
+        framebuffer.draw(&vertices);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(0, 0, 8, 8)));
+    }
+
+    #[test]
+    fn scissor_clips_fragments() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(viewport(rect(0, 0, 8, 8)));
+        viewport_state.scissors[0] = Some(Scissor {
+            render_area: rect(2, 3, 3, 2),
+        });
+        framebuffer.pipeline.set_viewport_state(viewport_state);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(2, 3, 3, 2)));
+    }
+
+    #[test]
+    fn viewport_with_offset_covers_its_rectangle() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(viewport(rect(4, 2, 4, 4)));
+        framebuffer.pipeline.set_viewport_state(viewport_state);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(4, 2, 4, 4)));
+    }
+
+    #[test]
+    fn viewport_with_negative_height_is_flipped_around_its_offset() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        let mut flipped = viewport(rect(4, 6, 4, 4));
+        flipped.extent.height = -4.0;
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(flipped);
+        framebuffer.pipeline.set_viewport_state(viewport_state);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(4, 2, 4, 4)));
+    }
+
+    #[test]
+    fn viewport_index_selects_viewport_and_scissor() {
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(viewport(rect(0, 0, 4, 8)));
+        viewport_state.scissors[0] = Some(Scissor {
+            render_area: rect(0, 0, 8, 8),
+        });
+        viewport_state.viewports[1] = Some(viewport(rect(4, 0, 4, 8)));
+        viewport_state.scissors[1] = Some(Scissor {
+            render_area: rect(0, 1, 8, 6),
+        });
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer.pipeline.set_viewport_state(viewport_state);
+        framebuffer.set_viewport_index(1);
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(4, 1, 4, 6)));
+    }
+
+    #[test]
+    fn out_of_range_viewport_index_selects_viewport_0() {
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(viewport(rect(0, 0, 4, 8)));
+        viewport_state.viewports[1] = Some(viewport(rect(4, 0, 4, 8)));
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer.pipeline.set_viewport_state(viewport_state);
+        for viewport_index in [2, MAX_VIEWPORTS, u32::MAX] {
+            framebuffer.set_viewport_index(viewport_index);
+            framebuffer.draw_full_screen(0.5);
+        }
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(0, 0, 4, 8)));
+    }
+
+    #[test]
+    fn framebuffer_extent_limits_rendering_with_and_without_attachments() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer.pipeline.set_framebuffer_extent(Extent2 {
+            width: 4,
+            height: 2,
+        });
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(0, 0, 4, 2)));
+
+        framebuffer
+            .pipeline
+            .unbind_render_target(RenderTargetIndex(0));
+        framebuffer.pipeline.unbind_depth_stencil_target();
+        framebuffer.draw_full_screen(0.5);
+    }
+
+    #[test]
+    fn dynamic_values_override_only_dynamic_pipeline_states() {
+        let mut pipeline = GraphicsPipeline::new();
+        pipeline.set_rasterization_state(RasterizationState {
+            line_width: 1.0,
+            depth_bias_constant_factor: 1.0,
+            ..Default::default()
+        });
+        pipeline.set_dynamic_states(DynamicStates {
+            line_width: true,
+            blend_constants: true,
+            stencil_reference: true,
+            ..Default::default()
+        });
+        pipeline.set_line_width(4.0);
+        pipeline.set_blend_constants([0.5; 4]);
+        pipeline.set_stencil_reference(
+            StencilFaces {
+                front: false,
+                back: true,
+            },
+            7,
+        );
+        pipeline.set_depth_bias(2.0, 0.0, 0.0);
+        pipeline.apply_dynamic_states();
+
+        assert_eq!(pipeline.rasterization_state.line_width, 4.0);
+        assert_eq!(pipeline.color_blend_state.blend_constants, [0.5; 4]);
+        assert_eq!(pipeline.depth_stencil_state.front.reference, 0);
+        assert_eq!(pipeline.depth_stencil_state.back.reference, 7);
+        // Depth bias is not dynamic, so value set by command is ignored.
+        assert_eq!(pipeline.rasterization_state.depth_bias_constant_factor, 1.0);
+    }
+
+    #[test]
+    fn dynamic_viewport_and_scissor_override_pipeline_ones() {
+        let mut framebuffer = Framebuffer::new(color_fragment_shader());
+        framebuffer.pipeline.set_dynamic_states(DynamicStates {
+            viewport: true,
+            scissor: true,
+            ..Default::default()
+        });
+        framebuffer
+            .pipeline
+            .set_viewports(0, vec![viewport(rect(4, 0, 4, 8))]);
+        framebuffer.pipeline.set_scissors(
+            0,
+            vec![Scissor {
+                render_area: rect(0, 2, 8, 4),
+            }],
+        );
+        framebuffer.draw_full_screen(0.5);
+        assert_eq!(framebuffer.covered_pixels(), pixels(rect(4, 2, 4, 4)));
+    }
+
+    #[test]
+    fn rendering_does_not_depend_on_number_of_worker_threads() {
+        // Same as running with `ICD_WORKER_THREADS=1` and with multiple threads.
+        let extent = Extent2 {
+            width: 3 * TILE_SIZE as u32 - 17,
+            height: 2 * TILE_SIZE as u32 + 5,
+        };
+        let vertices = [
+            triangle_vertices(
+                [(-0.9, -0.9), (0.8, -0.7), (-0.6, 0.9)],
+                0.5,
+                [1.0, 0.0, 0.0, 1.0],
+            ),
+            triangle_vertices(
+                [(-0.2, -1.0), (1.0, 0.3), (0.1, 1.0)],
+                0.3,
+                [0.0, 1.0, 0.0, 1.0],
+            ),
+            triangle_vertices(
+                [(-1.0, 0.1), (0.9, 0.9), (-0.4, -0.5)],
+                0.3,
+                [0.0, 0.0, 1.0, 0.5],
+            ),
+        ]
+        .concat();
+        let render = |threads| {
+            let mut framebuffer = Framebuffer::with_attachments(
+                extent,
+                &[(0, COLOR_FORMAT)],
+                color_fragment_shader(),
+            );
+            framebuffer.pipeline.worker_pool = WorkerPool::new(threads);
+            framebuffer
+                .pipeline
+                .set_depth_stencil_state(DepthStencilState {
+                    depth_test_enable: true,
+                    depth_write_enable: true,
+                    depth_compare_op: CompareOp::LessOrEqual,
+                    ..Default::default()
+                });
+            framebuffer.draw(&vertices);
+            (
+                framebuffer.color_bytes(0),
+                framebuffer.depth_stencil_bytes(),
+            )
+        };
+        let (colors, depths) = render(1);
+        assert!(colors.iter().any(|&byte| byte != 0));
+        for threads in [2, 4, 7] {
+            assert!(render(threads) == (colors.clone(), depths.clone()));
+        }
+    }
+}
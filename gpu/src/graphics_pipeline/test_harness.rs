@@ -0,0 +1,448 @@
+//! Fixtures shared by tests of `GraphicsPipeline`, rendering with shaders compiled from GLSL.
+
+use super::*;
+use crate::MemoryHandleStore;
+use common::consts::MAX_VERTEX_ATTRIBUTES;
+use common::graphics::{MemoryBinding, VertexAttribute, VertexBinding, VertexBindingNumber};
+use common::math::Extent3;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+fn compile_glsl(stage: &str, glsl_code: &str) -> Vec<u32> {
+    let temp_dir = assert_fs::TempDir::new().expect("temporary directory should be created");
+    let glsl_path = temp_dir.join(format!("shader.{stage}"));
+    let spv_path = temp_dir.join(format!("{stage}.spv"));
+    std::fs::write(&*glsl_path, glsl_code).expect("GLSL should be written");
+
+    let mut out = Command::new("glslangValidator");
+    let out = out.args([
+        "-V",
+        &glsl_path.to_string_lossy(),
+        "-o",
+        &spv_path.to_string_lossy(),
+    ]);
+    let out = out.current_dir(&temp_dir);
+    let out = out.output().expect("glslangValidator should run");
+    assert!(
+        out.status.success(),
+        "Didn't pass: stdout:\n{}\nstderr:\n{}",
+        String::from_utf8_lossy(&out.stdout),
+        String::from_utf8_lossy(&out.stderr),
+    );
+
+    let spv = std::fs::read(spv_path).expect("SPIR-V should be read");
+    spv.chunks_exact(4)
+        .map(|x| u32::from_ne_bytes(x.try_into().expect("chunk should be a word")))
+        .collect::<Vec<_>>()
+}
+
+fn vec4(color: [f32; 4]) -> String {
+    let [r, g, b, a] = color;
+    format!("vec4({r:?}, {g:?}, {b:?}, {a:?})")
+}
+
+/// Vertex shader passing through position at `Location` 0 and color at `Location` 1 and writing
+/// `viewport_index`.
+fn vertex_shader(viewport_index: u32) -> Shader {
+    let spv = compile_glsl(
+        "vert",
+        &format!(
+            r#"
+            #version 450
+            #extension GL_ARB_shader_viewport_layer_array : require
+            layout(location = 0) in vec4 position;
+            layout(location = 1) in vec4 color;
+            layout(location = 0) out vec4 out_color;
+            void main() {{
+                gl_Position = position;
+                out_color = color;
+                gl_ViewportIndex = {viewport_index};
+            }}
+            "#
+        ),
+    );
+    Shader::new("main", spv).expect("shader should compile")
+}
+
+/// Fragment shader writing interpolated color to each of `output_locations`, equivalent to:
+/// ```glsl
+/// layout(location = 0) in vec4 color;
+/// layout(location = N) out vec4 out_color_N;
+/// void main() {
+///     out_color_N = color;
+/// }
+/// ```
+#[derive(Default)]
+pub(super) struct FragmentShader {
+    pub(super) output_locations: Vec<u32>,
+    /// Writes `out_color_N = vec4(color)` for each `(N, color)`.
+    pub(super) constant_outputs: Vec<(u32, [f32; 4])>,
+    /// Writes `out_color_N = uvec4(color)` for each `(N, color)`.
+    pub(super) uint_outputs: Vec<(u32, [u32; 4])>,
+    /// Writes `gl_FragDepth = frag_depth`.
+    pub(super) frag_depth: Option<f32>,
+    /// Ends with `discard`.
+    pub(super) discard: bool,
+    /// Declares `layout(early_fragment_tests) in`.
+    pub(super) early_fragment_tests: bool,
+    /// Writes `out_color_0 = gl_FrontFacing ? RED : BLUE`.
+    pub(super) front_facing: bool,
+}
+
+impl FragmentShader {
+    fn glsl(&self) -> String {
+        let mut outputs = BTreeMap::new();
+        let mut body = vec![];
+        if self.front_facing {
+            outputs.insert(0, "vec4");
+            body.push(format!(
+                "out_color_0 = gl_FrontFacing ? {} : {};",
+                vec4(RED),
+                vec4(BLUE)
+            ));
+        }
+        for &location in &self.output_locations {
+            outputs.insert(location, "vec4");
+            body.push(format!("out_color_{location} = color;"));
+        }
+        for &(location, color) in &self.constant_outputs {
+            outputs.insert(location, "vec4");
+            body.push(format!("out_color_{location} = {};", vec4(color)));
+        }
+        for &(location, [r, g, b, a]) in &self.uint_outputs {
+            outputs.insert(location, "uvec4");
+            body.push(format!(
+                "out_color_{location} = uvec4({r}u, {g}u, {b}u, {a}u);"
+            ));
+        }
+        if let Some(depth) = self.frag_depth {
+            body.push(format!("gl_FragDepth = {depth:?};"));
+        }
+        if self.discard {
+            body.push("discard;".to_string());
+        }
+        let early_fragment_tests = if self.early_fragment_tests {
+            "layout(early_fragment_tests) in;"
+        } else {
+            ""
+        };
+        let outputs = outputs
+            .into_iter()
+            .map(|(location, type_)| {
+                format!("layout(location = {location}) out {type_} out_color_{location};")
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let body = body.join("\n");
+        format!(
+            r#"
+            #version 450
+            {early_fragment_tests}
+            layout(location = 0) in vec4 color;
+            {outputs}
+            void main() {{
+                {body}
+            }}
+            "#
+        )
+    }
+
+    pub(super) fn build(&self) -> Shader {
+        Shader::new("main", compile_glsl("frag", &self.glsl())).expect("shader should compile")
+    }
+}
+
+pub(super) const COLOR_FORMAT: Format = Format::R8G8B8A8Unorm;
+pub(super) const DEPTH_STENCIL_FORMAT: Format = Format::D32SfloatS8Uint;
+pub(super) const EXTENT: Extent2<u32> = Extent2 {
+    width: 8,
+    height: 8,
+};
+pub(super) const RED: [f32; 4] = [1.0, 0.0, 0.0, 1.0];
+pub(super) const BLUE: [f32; 4] = [0.0, 0.0, 1.0, 1.0];
+
+pub(super) fn image(memory: &mut Memory, format: Format, extent: Extent2<u32>) -> DescriptorImage {
+    let size = extent.width as u64 * extent.height as u64 * format.info().bytes_per_pixel as u64;
+    let mut binding = MemoryBinding::new();
+    binding.store(memory.allocate_memory(size), 0, size);
+    DescriptorImage {
+        binding,
+        extent: Extent3 {
+            width: extent.width,
+            height: extent.height,
+            depth: 1,
+        },
+    }
+}
+
+pub(super) fn rect(x: i32, y: i32, width: u32, height: u32) -> RenderArea {
+    RenderArea {
+        extent: Extent2 { width, height },
+        offset: Offset2 { x, y },
+    }
+}
+
+/// Buffer holding `bytes`.
+pub(super) fn buffer(memory: &mut Memory, bytes: &[u8]) -> DescriptorBuffer {
+    let mut binding = MemoryBinding::new();
+    binding.store(
+        memory.allocate_memory(bytes.len() as u64),
+        0,
+        bytes.len() as u64,
+    );
+    memory.write_bytes(bytes, &binding, 0);
+    DescriptorBuffer { binding }
+}
+
+/// Buffer holding `values` as native-endian floats.
+pub(super) fn float_buffer(
+    memory: &mut Memory,
+    values: impl IntoIterator<Item = f32>,
+) -> DescriptorBuffer {
+    let bytes = values
+        .into_iter()
+        .flat_map(|value| value.to_ne_bytes())
+        .collect::<Vec<_>>();
+    buffer(memory, &bytes)
+}
+
+/// Buffer holding `values` as native-endian 32-bit integers.
+pub(super) fn u32_buffer(
+    memory: &mut Memory,
+    values: impl IntoIterator<Item = u32>,
+) -> DescriptorBuffer {
+    let bytes = values
+        .into_iter()
+        .flat_map(|value| value.to_ne_bytes())
+        .collect::<Vec<_>>();
+    buffer(memory, &bytes)
+}
+
+/// Vertices of triangle with `color` and `corners` in normalized device coordinates.
+pub(super) fn triangle_vertices(
+    corners: [(f32, f32); 3],
+    z: f32,
+    color: [f32; 4],
+) -> [[[f32; 4]; 2]; 3] {
+    corners.map(|(x, y)| [[x, y, z, 1.0], color])
+}
+
+/// Pipeline drawing triangle lists with test shaders into its own color attachments cleared
+/// to 0 and depth/stencil attachment cleared to depth 1 and stencil 0.
+/// Vertices consist of position at `Location` 0 and color at `Location` 1.
+pub(super) struct Framebuffer {
+    pub(super) memory: Memory,
+    pub(super) pipeline: GraphicsPipeline,
+    pub(super) fragment_shader: FragmentShader,
+}
+
+impl Framebuffer {
+    /// Framebuffer of `EXTENT` with single color attachment at index 0.
+    pub(super) fn new(fragment_shader: FragmentShader) -> Self {
+        Self::with_attachments(EXTENT, &[(0, COLOR_FORMAT)], fragment_shader)
+    }
+
+    /// Framebuffer of `extent` with color attachments of `(index, format)`.
+    pub(super) fn with_attachments(
+        extent: Extent2<u32>,
+        color_attachments: &[(usize, Format)],
+        fragment_shader: FragmentShader,
+    ) -> Self {
+        let mut memory = Memory::new();
+        let mut pipeline = GraphicsPipeline::new();
+        pipeline.set_shader_state(ShaderState {
+            vertex_shader: Some(vertex_shader(0)),
+            fragment_shader: Some(fragment_shader.build()),
+        });
+        let mut vertex_input_state = VertexInputState {
+            attributes: [None; MAX_VERTEX_ATTRIBUTES as usize],
+            bindings: [None; MAX_VERTEX_BINDINGS as usize],
+        };
+        for location in 0..2 {
+            vertex_input_state.attributes[location as usize] = Some(VertexAttribute {
+                location,
+                binding: VertexBindingNumber(0),
+                format: Format::R32G32B32A32Sfloat,
+                offset: location * 16,
+            });
+        }
+        vertex_input_state.bindings[0] = Some(VertexBinding {
+            number: VertexBindingNumber(0),
+            stride: 32,
+            input_rate: VertexInputRate::Vertex,
+        });
+        pipeline.set_vertex_input_state(vertex_input_state);
+        pipeline.set_input_assembly_state(InputAssemblyState {
+            topology: PrimitiveTopology::TriangleList,
+            primitive_restart: false,
+        });
+        let area = rect(0, 0, extent.width, extent.height);
+        let mut viewport_state = ViewportState::default();
+        viewport_state.viewports[0] = Some(viewport(area));
+        pipeline.set_viewport_state(viewport_state);
+
+        for &(index, format) in color_attachments {
+            pipeline.bind_render_target(RenderTarget {
+                index: RenderTargetIndex(index),
+                format,
+                samples: 1,
+                image: image(&mut memory, format, extent),
+            });
+            pipeline.clear_render_target(
+                &mut memory,
+                RenderTargetIndex(index),
+                area,
+                Color::from_raw(0, 0, 0, 0),
+            );
+        }
+        pipeline.bind_depth_stencil_target(DepthStencilTarget {
+            format: DEPTH_STENCIL_FORMAT,
+            samples: 1,
+            image: image(&mut memory, DEPTH_STENCIL_FORMAT, extent),
+        });
+        pipeline.clear_depth_stencil_target(&mut memory, area, Some(1.0), Some(0));
+        Self {
+            memory,
+            pipeline,
+            fragment_shader,
+        }
+    }
+
+    /// Replaces vertex shader with one writing `viewport_index`.
+    pub(super) fn set_viewport_index(&mut self, viewport_index: u32) {
+        self.pipeline.set_shader_state(ShaderState {
+            vertex_shader: Some(vertex_shader(viewport_index)),
+            fragment_shader: Some(self.fragment_shader.build()),
+        });
+    }
+
+    pub(super) fn clear_depth(&mut self, depth: f32) {
+        let Some(target) = self.pipeline.depth_stencil_target.as_ref() else {
+            unreachable!()
+        };
+        let extent = target.image.extent;
+        self.pipeline.clear_depth_stencil_target(
+            &mut self.memory,
+            rect(0, 0, extent.width, extent.height),
+            Some(depth),
+            None,
+        );
+    }
+
+    pub(super) fn clear_stencil(&mut self, stencil: u32) {
+        let Some(target) = self.pipeline.depth_stencil_target.as_ref() else {
+            unreachable!()
+        };
+        let extent = target.image.extent;
+        self.pipeline.clear_depth_stencil_target(
+            &mut self.memory,
+            rect(0, 0, extent.width, extent.height),
+            None,
+            Some(stencil),
+        );
+    }
+
+    /// Binds vertex buffer of vertices with `(position, color)`.
+    pub(super) fn bind_vertices(&mut self, vertices: &[[[f32; 4]; 2]]) {
+        let buffer = float_buffer(
+            &mut self.memory,
+            vertices.iter().flatten().flatten().copied(),
+        );
+        self.pipeline.bind_vertex_buffer(VertexBuffer {
+            binding_number: VertexBindingNumber(0),
+            buffer,
+            offset: 0,
+        });
+    }
+
+    /// Draws triangles of vertices with `(position, color)`.
+    pub(super) fn draw(&mut self, vertices: &[[[f32; 4]; 2]]) {
+        self.bind_vertices(vertices);
+        self.pipeline
+            .draw_primitive(&mut self.memory, vertices.len() as u32, 1, 0, 0);
+    }
+
+    /// Draws red triangle at depth `z` covering whole view volume.
+    pub(super) fn draw_full_screen(&mut self, z: f32) {
+        self.draw(&triangle_vertices(
+            [(-1.0, -1.0), (3.0, -1.0), (-1.0, 3.0)],
+            z,
+            RED,
+        ));
+    }
+
+    /// Returns bytes of all pixels of color attachment at `index`.
+    pub(super) fn color_bytes(&self, index: usize) -> Vec<u8> {
+        let rt = &self.pipeline.render_targets[&RenderTargetIndex(index)];
+        self.memory.get_memory(&rt.image.binding).to_vec()
+    }
+
+    pub(super) fn depth_stencil_bytes(&self) -> Vec<u8> {
+        let Some(target) = self.pipeline.depth_stencil_target.as_ref() else {
+            unreachable!()
+        };
+        self.memory.get_memory(&target.image.binding).to_vec()
+    }
+
+    pub(super) fn color(&self, index: usize, x: u64, y: u64) -> [u8; 4] {
+        let rt = &self.pipeline.render_targets[&RenderTargetIndex(index)];
+        let offset = texel_offset(&rt.image, rt.format, 1, x, y, 0);
+        let mut color = [0; 4];
+        color.copy_from_slice(self.memory.read_bytes(&rt.image.binding, offset, 4));
+        color
+    }
+
+    pub(super) fn depth_stencil(&self, x: u64, y: u64) -> (f32, u8) {
+        let Some(target) = self.pipeline.depth_stencil_target.as_ref() else {
+            unreachable!()
+        };
+        let size = target.format.info().bytes_per_pixel as u64;
+        let offset = texel_offset(&target.image, target.format, 1, x, y, 0);
+        let texel = self.memory.read_bytes(&target.image.binding, offset, size);
+        (
+            target.format.depth_from_bytes(texel),
+            target.format.stencil_from_bytes(texel),
+        )
+    }
+
+    /// Returns pixels of color attachment 0 written by draws.
+    pub(super) fn covered_pixels(&self) -> Vec<(u64, u64)> {
+        let extent = self.pipeline.render_targets[&RenderTargetIndex(0)]
+            .image
+            .extent;
+        pixels(rect(0, 0, extent.width, extent.height))
+            .into_iter()
+            .filter(|&(x, y)| self.color(0, x, y) != [0; 4])
+            .collect()
+    }
+}
+
+pub(super) fn pixels(area: RenderArea) -> Vec<(u64, u64)> {
+    let x = area.offset.x as u64;
+    let y = area.offset.y as u64;
+    (y..y + area.extent.height as u64)
+        .flat_map(|y| (x..x + area.extent.width as u64).map(move |x| (x, y)))
+        .collect()
+}
+
+pub(super) fn viewport(area: RenderArea) -> Viewport {
+    Viewport {
+        offset: Offset2 {
+            x: area.offset.x as f32,
+            y: area.offset.y as f32,
+        },
+        extent: Extent2 {
+            width: area.extent.width as f32,
+            height: area.extent.height as f32,
+        },
+        depth: Range2 { min: 0.0, max: 1.0 },
+    }
+}
+
+/// Fragment shader writing interpolated color to attachment 0.
+pub(super) fn color_fragment_shader() -> FragmentShader {
+    FragmentShader {
+        output_locations: vec![0],
+        ..Default::default()
+    }
+}
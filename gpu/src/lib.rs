@@ -2,12 +2,20 @@ extern crate common;
 extern crate core;
 extern crate shader;
 
+pub mod blending;
+pub mod clipping;
 pub mod gpu;
 pub mod graphics_pipeline;
 pub mod memory;
+pub mod primitive_assembly;
 pub mod rasterization;
+pub mod tiling;
 
+pub use blending::*;
+pub use clipping::*;
 pub use gpu::*;
 pub use graphics_pipeline::*;
 pub use memory::*;
+pub use primitive_assembly::*;
 pub use rasterization::*;
+pub use tiling::*;
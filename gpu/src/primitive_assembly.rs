@@ -0,0 +1,278 @@
+use std::ops::Range;
+
+use crate::PrimitiveTopology;
+
+/// Splits vertex stream into independent triangles.
+/// Each strip is assembled separately, so primitive restart splits strips and fans.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#drawing-primitive-topologies
+pub fn assemble_triangles<T: Copy>(
+    topology: PrimitiveTopology,
+    vertices: &[T],
+    strips: &[Range<usize>],
+) -> Vec<[T; 3]> {
+    strips
+        .iter()
+        .flat_map(|strip| assemble_triangle_strip(topology, &vertices[strip.clone()]))
+        .collect()
+}
+
+fn assemble_triangle_strip<T: Copy>(topology: PrimitiveTopology, vertices: &[T]) -> Vec<[T; 3]> {
+    match topology {
+        PrimitiveTopology::TriangleList => vertices
+            .chunks_exact(3)
+            .map(|triangle| [triangle[0], triangle[1], triangle[2]])
+            .collect(),
+        PrimitiveTopology::TriangleStrip => vertices
+            .windows(3)
+            .enumerate()
+            .map(|(i, triangle)| {
+                // Every other triangle has its last two vertices swapped to preserve winding,
+                // first vertex is the provoking vertex.
+                if i % 2 == 0 {
+                    [triangle[0], triangle[1], triangle[2]]
+                } else {
+                    [triangle[0], triangle[2], triangle[1]]
+                }
+            })
+            .collect(),
+        PrimitiveTopology::TriangleFan => vertices
+            .windows(2)
+            .skip(1)
+            .map(|triangle| [triangle[0], triangle[1], vertices[0]])
+            .collect(),
+        // Without geometry shader adjacent vertices are dropped.
+        PrimitiveTopology::TriangleListWithAdjacency
+        | PrimitiveTopology::TriangleStripWithAdjacency => {
+            assemble_triangle_strip_with_adjacency(topology, vertices)
+                .into_iter()
+                .map(|[v0, _, v1, _, v2, _]| [v0, v1, v2])
+                .collect()
+        }
+        _ => unreachable!("{:?} is not a triangle topology", topology),
+    }
+}
+
+/// Assembles triangles with adjacency, each triangle is
+/// `[v0, adjacent to v0 v1, v1, adjacent to v1 v2, v2, adjacent to v2 v0]`.
+fn assemble_triangle_strip_with_adjacency<T: Copy>(
+    topology: PrimitiveTopology,
+    vertices: &[T],
+) -> Vec<[T; 6]> {
+    match topology {
+        PrimitiveTopology::TriangleListWithAdjacency => vertices
+            .chunks_exact(6)
+            .map(|triangle| {
+                [
+                    triangle[0],
+                    triangle[1],
+                    triangle[2],
+                    triangle[3],
+                    triangle[4],
+                    triangle[5],
+                ]
+            })
+            .collect(),
+        PrimitiveTopology::TriangleStripWithAdjacency => {
+            // Triangles are formed by even vertices as in triangle strip, odd vertices are
+            // adjacent to edges shared with previous triangle, next triangle and the third edge.
+            let count = (vertices.len() / 2).saturating_sub(2);
+            (0..count)
+                .map(|i| {
+                    let v = |index: usize| vertices[index];
+                    let (first, last) = (i == 0, i + 1 == count);
+                    let previous = if first { v(1) } else { v(2 * i - 2) };
+                    let next = if last { v(2 * i + 5) } else { v(2 * i + 6) };
+                    if i % 2 == 0 {
+                        [
+                            v(2 * i),
+                            previous,
+                            v(2 * i + 2),
+                            next,
+                            v(2 * i + 4),
+                            v(2 * i + 3),
+                        ]
+                    } else {
+                        [
+                            v(2 * i),
+                            v(2 * i + 3),
+                            v(2 * i + 4),
+                            next,
+                            v(2 * i + 2),
+                            previous,
+                        ]
+                    }
+                })
+                .collect()
+        }
+        _ => unreachable!("{:?} is not a triangle topology with adjacency", topology),
+    }
+}
+
+/// Splits vertex stream into independent line segments.
+pub fn assemble_lines<T: Copy>(
+    topology: PrimitiveTopology,
+    vertices: &[T],
+    strips: &[Range<usize>],
+) -> Vec<[T; 2]> {
+    strips
+        .iter()
+        .flat_map(|strip| assemble_line_strip(topology, &vertices[strip.clone()]))
+        .collect()
+}
+
+fn assemble_line_strip<T: Copy>(topology: PrimitiveTopology, vertices: &[T]) -> Vec<[T; 2]> {
+    match topology {
+        PrimitiveTopology::LineList => vertices
+            .chunks_exact(2)
+            .map(|line| [line[0], line[1]])
+            .collect(),
+        PrimitiveTopology::LineStrip => {
+            vertices.windows(2).map(|line| [line[0], line[1]]).collect()
+        }
+        // Without geometry shader adjacent vertices are dropped.
+        PrimitiveTopology::LineListWithAdjacency | PrimitiveTopology::LineStripWithAdjacency => {
+            assemble_line_strip_with_adjacency(topology, vertices)
+                .into_iter()
+                .map(|[_, v0, v1, _]| [v0, v1])
+                .collect()
+        }
+        _ => unreachable!("{:?} is not a line topology", topology),
+    }
+}
+
+/// Assembles line segments with adjacency, each line is `[adjacent to v0, v0, v1, adjacent to v1]`.
+fn assemble_line_strip_with_adjacency<T: Copy>(
+    topology: PrimitiveTopology,
+    vertices: &[T],
+) -> Vec<[T; 4]> {
+    match topology {
+        PrimitiveTopology::LineListWithAdjacency => vertices
+            .chunks_exact(4)
+            .map(|line| [line[0], line[1], line[2], line[3]])
+            .collect(),
+        PrimitiveTopology::LineStripWithAdjacency => vertices
+            .windows(4)
+            .map(|line| [line[0], line[1], line[2], line[3]])
+            .collect(),
+        _ => unreachable!("{:?} is not a line topology with adjacency", topology),
+    }
+}
+
+/// Splits vertex stream into patches of `control_points` vertices.
+/// Patches are consumed only by tessellation, so they are not drawn until it is implemented.
+pub fn assemble_patches<T: Copy>(
+    vertices: &[T],
+    strips: &[Range<usize>],
+    control_points: u32,
+) -> Vec<Vec<T>> {
+    assert_ne!(control_points, 0);
+    strips
+        .iter()
+        .flat_map(|strip| vertices[strip.clone()].chunks_exact(control_points as usize))
+        .map(|patch| patch.to_vec())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn triangle_strip_preserves_winding_of_odd_triangles() {
+        let vertices = (0..5).collect::<Vec<u32>>();
+        let strip = 0..vertices.len();
+        assert_eq!(
+            assemble_triangles(
+                PrimitiveTopology::TriangleStrip,
+                &vertices,
+                std::slice::from_ref(&strip)
+            ),
+            vec![[0, 1, 2], [1, 3, 2], [2, 3, 4]]
+        );
+    }
+
+    #[test]
+    fn triangle_fan_shares_first_vertex() {
+        let vertices = (0..5).collect::<Vec<u32>>();
+        let strip = 0..vertices.len();
+        assert_eq!(
+            assemble_triangles(
+                PrimitiveTopology::TriangleFan,
+                &vertices,
+                std::slice::from_ref(&strip)
+            ),
+            vec![[1, 2, 0], [2, 3, 0], [3, 4, 0]]
+        );
+        // Fan needs at least 3 vertices.
+        let strip = 0..2;
+        assert!(assemble_triangles(
+            PrimitiveTopology::TriangleFan,
+            &vertices,
+            std::slice::from_ref(&strip)
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn triangle_strip_with_adjacency() {
+        let vertices = (0..10).collect::<Vec<u32>>();
+        assert_eq!(
+            assemble_triangle_strip_with_adjacency(
+                PrimitiveTopology::TriangleStripWithAdjacency,
+                &vertices
+            ),
+            vec![[0, 1, 2, 6, 4, 3], [2, 5, 6, 8, 4, 0], [4, 2, 6, 9, 8, 7],]
+        );
+        let strip = 0..vertices.len();
+        assert_eq!(
+            assemble_triangles(
+                PrimitiveTopology::TriangleStripWithAdjacency,
+                &vertices,
+                std::slice::from_ref(&strip)
+            ),
+            vec![[0, 2, 4], [2, 6, 4], [4, 6, 8]]
+        );
+    }
+
+    #[test]
+    fn triangle_list_with_adjacency_restarts_strips() {
+        let vertices = (0..13).collect::<Vec<u32>>();
+        // Incomplete triangle at end of strip is dropped.
+        let strips = [0..6, 6..13];
+        assert_eq!(
+            assemble_triangles(
+                PrimitiveTopology::TriangleListWithAdjacency,
+                &vertices,
+                &strips
+            ),
+            vec![[0, 2, 4], [6, 8, 10]]
+        );
+    }
+
+    #[test]
+    fn lines_with_adjacency_drop_adjacent_vertices() {
+        let vertices = (0..9).collect::<Vec<u32>>();
+        let strips = [0..5, 5..9];
+        assert_eq!(
+            assemble_lines(
+                PrimitiveTopology::LineStripWithAdjacency,
+                &vertices,
+                &strips
+            ),
+            vec![[1, 2], [2, 3], [6, 7]]
+        );
+        assert_eq!(
+            assemble_lines(PrimitiveTopology::LineListWithAdjacency, &vertices, &strips),
+            vec![[1, 2], [6, 7]]
+        );
+    }
+
+    #[test]
+    fn patches_are_grouped_by_control_points() {
+        let vertices = (0..8).collect::<Vec<u32>>();
+        assert_eq!(
+            assemble_patches(&vertices, &[0..5, 5..8], 3),
+            vec![vec![0, 1, 2], vec![5, 6, 7]]
+        );
+    }
+}
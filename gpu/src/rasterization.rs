@@ -1,65 +1,926 @@
-use common::math::{Color, Fragment, Position, Vertex};
-use log::warn;
-
-pub fn draw_line_bresenham(v0: Vertex, v1: Vertex, fragments: &mut Vec<Fragment>, color: Color) {
-    // Bresenham's line algorithm
-    warn!("TODO: Replace line segment rasterization.");
-    // https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-lines-basic
-    let v0 = v0.position;
-    let v1 = v1.position;
-
-    let (mut x0, mut y0) = (
-        (v0.get_as_sfloat32(0)) as i32,
-        (v0.get_as_sfloat32(1)) as i32,
-    );
-    let (mut x1, mut y1) = (
-        (v1.get_as_sfloat32(0)) as i32,
-        (v1.get_as_sfloat32(1)) as i32,
-    );
-    let steep = if (y1 - y0).abs() > (x1 - x0).abs() {
-        std::mem::swap(&mut x0, &mut y0);
-        std::mem::swap(&mut x1, &mut y1);
-        true
+use crate::PixelRect;
+use common::consts::{MAX_SAMPLE_COUNT, MAX_VARYING_LOCATIONS};
+use common::math::{Fragment, FragmentQuad, Position, Vector4};
+use hashbrown::HashMap;
+use shader::glsl::{Interpolation, VertexShaderOutput};
+
+/// Interpolation decorations of fragment shader inputs, indexed by `Location`.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct Interpolations {
+    pub modes: [Interpolation; MAX_VARYING_LOCATIONS as usize],
+    /// Inputs decorated with `Centroid`, interpolated within covered area of pixel.
+    pub centroid: [bool; MAX_VARYING_LOCATIONS as usize],
+    /// Locations read by fragment shader, other varyings are not interpolated.
+    pub inputs: [bool; MAX_VARYING_LOCATIONS as usize],
+}
+
+impl Interpolations {
+    fn has_centroid(&self) -> bool {
+        self.centroid.contains(&true)
+    }
+}
+
+/// Copies flat varyings of provoking vertex to all `vertices` of primitive.
+pub fn apply_provoking_vertex(
+    vertices: &mut [VertexShaderOutput],
+    provoking_vertex: &VertexShaderOutput,
+    interpolations: &Interpolations,
+) {
+    for vertex in vertices {
+        for (location, interpolation) in interpolations.modes.iter().enumerate() {
+            if *interpolation == Interpolation::Flat {
+                vertex.varyings[location] = provoking_vertex.varyings[location];
+            }
+        }
+    }
+}
+
+/// Interpolation of vertex outputs using screen-space barycentric `weights`.
+/// Vertex positions are in framebuffer coordinates with w component holding 1/w of clip coordinates.
+/// Flat varyings are taken from first vertex, see [`apply_provoking_vertex`].
+/// Returns interpolated 1/w and varyings, those not read by fragment shader are left default.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-polygons-basic
+fn interpolate_varyings<const N: usize>(
+    vertices: &[VertexShaderOutput; N],
+    weights: [f32; N],
+    interpolations: &Interpolations,
+) -> (f32, [Vector4; MAX_VARYING_LOCATIONS as usize]) {
+    let inv_w = (0..N)
+        .map(|i| weights[i] * vertices[i].position.get_as_sfloat32(3))
+        .sum::<f32>();
+    let mut varyings = [Vector4::default(); MAX_VARYING_LOCATIONS as usize];
+    for (location, varying) in varyings.iter_mut().enumerate() {
+        if !interpolations.inputs[location] {
+            continue;
+        }
+        let perspective = match interpolations.modes[location] {
+            Interpolation::Perspective => true,
+            Interpolation::NoPerspective => false,
+            Interpolation::Flat => {
+                *varying = vertices[0].varyings[location];
+                continue;
+            }
+        };
+        let mut components = [0.0f32; 4];
+        for i in 0..N {
+            let weight = if perspective {
+                weights[i] * vertices[i].position.get_as_sfloat32(3) / inv_w
+            } else {
+                weights[i]
+            };
+            let values = vertices[i].varyings[location].get_as_f32_array();
+            for (component, value) in components.iter_mut().zip(values) {
+                *component = weight.mul_add(value, *component);
+            }
+        }
+        let [r, g, b, a] = components;
+        *varying = Vector4::from_sfloat32_raw(r, g, b, a);
+    }
+    (inv_w, varyings)
+}
+
+/// Standard sample locations within pixel for `samples` per pixel.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-multisampling
+pub const fn sample_locations(samples: u32) -> &'static [(f32, f32)] {
+    match samples {
+        1 => &[(0.5, 0.5)],
+        2 => &[(0.75, 0.75), (0.25, 0.25)],
+        4 => &[
+            (0.375, 0.125),
+            (0.875, 0.375),
+            (0.125, 0.625),
+            (0.625, 0.875),
+        ],
+        8 => &[
+            (0.5625, 0.3125),
+            (0.4375, 0.6875),
+            (0.8125, 0.5625),
+            (0.3125, 0.1875),
+            (0.1875, 0.8125),
+            (0.0625, 0.4375),
+            (0.6875, 0.9375),
+            (0.9375, 0.0625),
+        ],
+        _ => unreachable!(),
+    }
+}
+
+/// Sample mask with all `samples` covered.
+const fn full_sample_mask(samples: u32) -> u32 {
+    (1 << samples) - 1
+}
+
+/// Location within pixel where centroid inputs are interpolated, if it differs from pixel
+/// center. Partially covered pixels use their first covered sample.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#shaders-interpolation-decorations
+fn centroid_location(
+    samples: u32,
+    sample_mask: u32,
+    interpolations: &Interpolations,
+) -> Option<(f32, f32)> {
+    if sample_mask == 0 || sample_mask == full_sample_mask(samples) {
+        return None;
+    }
+    interpolations
+        .has_centroid()
+        .then(|| sample_locations(samples)[sample_mask.trailing_zeros() as usize])
+}
+
+/// Replaces centroid inputs of `varyings` with values interpolated at centroid location.
+fn apply_centroid(
+    varyings: &mut [Vector4; MAX_VARYING_LOCATIONS as usize],
+    centroid_varyings: &[Vector4; MAX_VARYING_LOCATIONS as usize],
+    interpolations: &Interpolations,
+) {
+    for (location, &centroid) in interpolations.centroid.iter().enumerate() {
+        if centroid {
+            varyings[location] = centroid_varyings[location];
+        }
+    }
+}
+
+pub fn draw_line(
+    vertices: [VertexShaderOutput; 2],
+    line_width: f32,
+    bounds: PixelRect,
+    samples: u32,
+    interpolations: &Interpolations,
+    fragments: &mut Vec<Fragment>,
+) {
+    if samples > 1 {
+        draw_rectangular_line(
+            vertices,
+            line_width,
+            bounds,
+            samples,
+            interpolations,
+            fragments,
+        );
+        return;
+    }
+
+    // Non-strict line rasterization using diamond-exit rule.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-lines-bresenham
+    let [p0, p1] = vertices.map(|v| {
+        (
+            v.position.get_as_sfloat32(0),
+            v.position.get_as_sfloat32(1),
+            v.position.get_as_sfloat32(2),
+        )
+    });
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let length_squared = dx.mul_add(dx, dy * dy);
+    if length_squared == 0.0 {
+        return;
+    }
+
+    // Iterate over major axis, (major, minor) coordinates are swapped for y-major lines.
+    let x_major = dx.abs() >= dy.abs();
+    let swizzle = |x: f32, y: f32| if x_major { (x, y) } else { (y, x) };
+    let (a0, b0) = swizzle(p0.0, p0.1);
+    let (a1, b1) = swizzle(p1.0, p1.1);
+    let (major_bounds, minor_bounds) = if x_major {
+        (bounds.min_x..bounds.max_x, bounds.min_y..bounds.max_y)
     } else {
-        false
+        (bounds.min_y..bounds.max_y, bounds.min_x..bounds.max_x)
     };
-    if x0 > x1 {
-        std::mem::swap(&mut x0, &mut x1);
-        std::mem::swap(&mut y0, &mut y1);
-    }
-
-    let d_err = (y1 - y0).abs();
-    let d_x = x1 - x0;
-    let y_step = if y0 > y1 { -1_i32 } else { 1 };
-
-    let mut err = d_x / 2; // Pixel center.
-    let mut y = y0;
-    for x in x0..=x1 {
-        // TODO: z_screen
-        let (x_fragment, y_fragment) = if steep {
-            (y as f32, x as f32)
-        } else {
-            (x as f32, y as f32)
-        };
-        fragments.push(Fragment {
-            position: Position::from_sfloat32_raw(x_fragment, y_fragment, 0.0f32, 1.0f32), // TODO: Get z and w from vertex shader.
-            color,
-        });
-        err -= d_err;
-        if err < 0 {
-            y += y_step;
-            err += d_x;
+
+    // Wide lines replicate fragments along minor axis.
+    let width = (line_width.round() as i64).max(1);
+
+    let major_min = (a0.min(a1).floor() as i64).max(major_bounds.start);
+    let major_max = (a0.max(a1).floor() as i64).min(major_bounds.end - 1);
+    for major in major_min..=major_max {
+        let major_center = major as f32 + 0.5;
+        let t = ((major_center - a0) / (a1 - a0)).clamp(0.0, 1.0);
+        let minor_on_line = (b1 - b0).mul_add(t, b0).floor() as i64;
+        for minor in minor_on_line - 1..=minor_on_line + 1 {
+            let (x, y) = swizzle(major as f32, minor as f32);
+            if !is_diamond_exited(p0, p1, (x + 0.5, y + 0.5)) {
+                continue;
+            }
+
+            // Project fragment center onto line segment.
+            let t = ((x + 0.5 - p0.0).mul_add(dx, (y + 0.5 - p0.1) * dy) / length_squared)
+                .clamp(0.0, 1.0);
+            let z = (p1.2 - p0.2).mul_add(t, p0.2);
+            let (inv_w, varyings) = interpolate_varyings(&vertices, [1.0 - t, t], interpolations);
+
+            for i in 0..width {
+                let minor = minor - (width - 1) / 2 + i;
+                if !minor_bounds.contains(&minor) {
+                    continue;
+                }
+                let (x, y) = swizzle(major as f32, minor as f32);
+                fragments.push(Fragment {
+                    position: Position::from_sfloat32_raw(x + 0.5, y + 0.5, z, inv_w),
+                    varyings,
+                    front_facing: true,
+                    sample_mask: full_sample_mask(samples),
+                    sample_depths: [z; MAX_SAMPLE_COUNT as usize],
+                });
+            }
+        }
+    }
+}
+
+/// Multisampled lines are rasterized as rectangles of `line_width` centered on segment,
+/// coverage and depth are determined per sample.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-lines-basic
+fn draw_rectangular_line(
+    vertices: [VertexShaderOutput; 2],
+    line_width: f32,
+    bounds: PixelRect,
+    samples: u32,
+    interpolations: &Interpolations,
+    fragments: &mut Vec<Fragment>,
+) {
+    let [p0, p1] = vertices.map(|v| {
+        (
+            v.position.get_as_sfloat32(0),
+            v.position.get_as_sfloat32(1),
+            v.position.get_as_sfloat32(2),
+        )
+    });
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let length = dx.hypot(dy);
+    if length == 0.0 {
+        return;
+    }
+    let (ux, uy) = (dx / length, dy / length);
+    let half_width = line_width.max(1.0) / 2.0;
+
+    // Distance of point along segment from its start and distance from segment.
+    let project = |x: f32, y: f32| {
+        let (rx, ry) = (x - p0.0, y - p0.1);
+        (rx.mul_add(ux, ry * uy), rx.mul_add(-uy, ry * ux))
+    };
+    // Interpolates attributes at point projected onto segment.
+    let interpolate = |along: f32| {
+        let t = (along / length).clamp(0.0, 1.0);
+        let z = (p1.2 - p0.2).mul_add(t, p0.2);
+        let (inv_w, varyings) = interpolate_varyings(&vertices, [1.0 - t, t], interpolations);
+        (z, inv_w, varyings)
+    };
+
+    let min_x = ((p0.0.min(p1.0) - half_width).floor() as i64).max(bounds.min_x);
+    let min_y = ((p0.1.min(p1.1) - half_width).floor() as i64).max(bounds.min_y);
+    let max_x = ((p0.0.max(p1.0) + half_width).floor() as i64).min(bounds.max_x - 1);
+    let max_y = ((p0.1.max(p1.1) + half_width).floor() as i64).min(bounds.max_y - 1);
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            let mut sample_mask = 0;
+            let mut sample_depths = [0.0f32; MAX_SAMPLE_COUNT as usize];
+            for (sample, &location) in sample_locations(samples).iter().enumerate() {
+                let (along, across) = project(x as f32 + location.0, y as f32 + location.1);
+                // End of segment is excluded, so that segments of strip do not overlap.
+                if (0.0..length).contains(&along) && across.abs() < half_width {
+                    sample_mask |= 1 << sample;
+                    sample_depths[sample] = interpolate(along).0;
+                }
+            }
+            if sample_mask == 0 {
+                continue;
+            }
+
+            // Fragment is shaded once at pixel center.
+            let (along, _) = project(x as f32 + 0.5, y as f32 + 0.5);
+            let (z, inv_w, mut varyings) = interpolate(along);
+            if let Some(location) = centroid_location(samples, sample_mask, interpolations) {
+                let (along, _) = project(x as f32 + location.0, y as f32 + location.1);
+                apply_centroid(&mut varyings, &interpolate(along).2, interpolations);
+            }
+            fragments.push(Fragment {
+                position: Position::from_sfloat32_raw(x as f32 + 0.5, y as f32 + 0.5, z, inv_w),
+                varyings,
+                front_facing: true,
+                sample_mask,
+                sample_depths,
+            });
+        }
+    }
+}
+
+/// Returns true if line segment exits diamond region of fragment centered at `center`.
+fn is_diamond_exited(p0: (f32, f32, f32), p1: (f32, f32, f32), center: (f32, f32)) -> bool {
+    let distance = |x: f32, y: f32| (x - center.0).abs() + (y - center.1).abs();
+    if distance(p1.0, p1.1) < 0.5 {
+        // Segment ends inside diamond.
+        return false;
+    }
+
+    // Manhattan distance to segment is piecewise linear, its minimum lies on breakpoints.
+    let (dx, dy) = (p1.0 - p0.0, p1.1 - p0.1);
+    let mut candidates = [0.0f32, 1.0f32, 0.0f32, 0.0f32];
+    if dx != 0.0 {
+        candidates[2] = ((center.0 - p0.0) / dx).clamp(0.0, 1.0);
+    }
+    if dy != 0.0 {
+        candidates[3] = ((center.1 - p0.1) / dy).clamp(0.0, 1.0);
+    }
+    candidates
+        .iter()
+        .any(|&t| distance(dx.mul_add(t, p0.0), dy.mul_add(t, p0.1)) < 0.5)
+}
+
+/// Number of fractional bits used for sub-pixel vertex positions.
+const SUB_PIXEL_BITS: u32 = 8;
+const SUB_PIXEL_SCALE: f32 = (1 << SUB_PIXEL_BITS) as f32;
+
+/// Framebuffer position in fixed-point sub-pixel units.
+#[derive(Debug, Copy, Clone)]
+struct FixedPoint {
+    x: i64,
+    y: i64,
+}
+
+impl FixedPoint {
+    fn from_vertex(vertex: &VertexShaderOutput) -> Self {
+        Self {
+            x: (vertex.position.get_as_sfloat32(0) * SUB_PIXEL_SCALE).round() as i64,
+            y: (vertex.position.get_as_sfloat32(1) * SUB_PIXEL_SCALE).round() as i64,
+        }
+    }
+
+    /// Center of pixel (x, y).
+    const fn pixel_center(x: i64, y: i64) -> Self {
+        let half = 1 << (SUB_PIXEL_BITS - 1);
+        Self {
+            x: (x << SUB_PIXEL_BITS) + half,
+            y: (y << SUB_PIXEL_BITS) + half,
+        }
+    }
+
+    /// Sample `location` within pixel (x, y).
+    fn pixel_sample(x: i64, y: i64, location: (f32, f32)) -> Self {
+        Self {
+            x: (x << SUB_PIXEL_BITS) + (location.0 * SUB_PIXEL_SCALE) as i64,
+            y: (y << SUB_PIXEL_BITS) + (location.1 * SUB_PIXEL_SCALE) as i64,
+        }
+    }
+}
+
+/// Edge function, twice the signed area of triangle (a, b, p).
+const fn edge_function(a: FixedPoint, b: FixedPoint, p: FixedPoint) -> i64 {
+    (b.x - a.x) * (p.y - a.y) - (b.y - a.y) * (p.x - a.x)
+}
+
+/// Top-left fill rule for edge of triangle with positive area.
+const fn is_top_left_edge(a: FixedPoint, b: FixedPoint) -> bool {
+    let is_top = a.y == b.y && b.x > a.x;
+    let is_left = b.y < a.y;
+    is_top || is_left
+}
+
+pub fn draw_triangle(
+    mut vertices: [VertexShaderOutput; 3],
+    bounds: PixelRect,
+    samples: u32,
+    interpolations: &Interpolations,
+    fragments: &mut Vec<Fragment>,
+) {
+    // Edge-function (half-space) triangle rasterization.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-polygons-basic
+    let mut fixed_points = vertices.map(|v| FixedPoint::from_vertex(&v));
+    let mut area = edge_function(fixed_points[0], fixed_points[1], fixed_points[2]);
+    if area == 0 {
+        return;
+    }
+    if area < 0 {
+        // Keep first (provoking) vertex in place.
+        vertices.swap(1, 2);
+        fixed_points.swap(1, 2);
+        area = -area;
+    }
+    let [v0, v1, v2] = fixed_points;
+    let [z0, z1, z2] = vertices.map(|v| v.position.get_as_sfloat32(2));
+
+    // Samples lying exactly on an edge are covered only if it is a top or left edge.
+    let bias = |a, b| if is_top_left_edge(a, b) { 0 } else { -1 };
+    let (bias0, bias1, bias2) = (bias(v1, v2), bias(v2, v0), bias(v0, v1));
+
+    // Screen-space barycentric coordinates, if point is covered.
+    let barycentric = |p: FixedPoint| {
+        let w0 = edge_function(v1, v2, p);
+        let w1 = edge_function(v2, v0, p);
+        let w2 = edge_function(v0, v1, p);
+        let covered = w0 + bias0 >= 0 && w1 + bias1 >= 0 && w2 + bias2 >= 0;
+        (covered, [w0, w1, w2].map(|w| w as f32 / area as f32))
+    };
+
+    // Bounding box of pixels which may be covered, clamped to `bounds`.
+    let min_x = (v0.x.min(v1.x).min(v2.x) >> SUB_PIXEL_BITS).max(bounds.min_x);
+    let min_y = (v0.y.min(v1.y).min(v2.y) >> SUB_PIXEL_BITS).max(bounds.min_y);
+    let max_x = (v0.x.max(v1.x).max(v2.x) >> SUB_PIXEL_BITS).min(bounds.max_x - 1);
+    let max_y = (v0.y.max(v1.y).max(v2.y) >> SUB_PIXEL_BITS).min(bounds.max_y - 1);
+
+    // Pixels are visited in 2x2 quads, uncovered pixels of partially covered quad are emitted as
+    // helper invocations with extrapolated varyings, so that derivatives can be computed.
+    let fragment = |x: i64, y: i64| {
+        // Coverage and depth are determined per sample.
+        let mut sample_mask = 0;
+        let mut sample_depths = [0.0f32; MAX_SAMPLE_COUNT as usize];
+        if bounds.contains(x, y) {
+            for (sample, &location) in sample_locations(samples).iter().enumerate() {
+                let (covered, [l0, l1, l2]) = barycentric(FixedPoint::pixel_sample(x, y, location));
+                if covered {
+                    sample_mask |= 1 << sample;
+                    sample_depths[sample] = l0.mul_add(z0, l1.mul_add(z1, l2 * z2));
+                }
+            }
+        }
+
+        // Fragment is shaded once at pixel center.
+        let (_, [l0, l1, l2]) = barycentric(FixedPoint::pixel_center(x, y));
+        let z = l0.mul_add(z0, l1.mul_add(z1, l2 * z2));
+        let (inv_w, mut varyings) = interpolate_varyings(&vertices, [l0, l1, l2], interpolations);
+        if let Some(location) = centroid_location(samples, sample_mask, interpolations) {
+            let (_, weights) = barycentric(FixedPoint::pixel_sample(x, y, location));
+            let (_, centroid_varyings) = interpolate_varyings(&vertices, weights, interpolations);
+            apply_centroid(&mut varyings, &centroid_varyings, interpolations);
+        }
+        Fragment {
+            position: Position::from_sfloat32_raw(x as f32 + 0.5, y as f32 + 0.5, z, inv_w),
+            varyings,
+            front_facing: true,
+            sample_mask,
+            sample_depths,
+        }
+    };
+    for quad_y in (min_y & !1..=max_y).step_by(2) {
+        for quad_x in (min_x & !1..=max_x).step_by(2) {
+            let quad: FragmentQuad = std::array::from_fn(|lane| {
+                fragment(quad_x + lane as i64 % 2, quad_y + lane as i64 / 2)
+            });
+            if quad.iter().all(Fragment::is_helper_invocation) {
+                continue;
+            }
+            fragments.extend(quad);
         }
     }
 }
 
+/// Groups fragments of single primitive into quads, see [`FragmentQuad`].
+///
+/// Fragments at the same pixel are placed in separate quads, keeping their order.
+/// Missing pixels of quad are filled with helper invocations copying first fragment of quad.
+/// Unlike helpers of triangles extrapolated in [`draw_triangle`], varyings of these helpers are
+/// not extrapolated along the line, so derivatives across quads of lines and points are zero.
+pub fn assemble_quads(fragments: Vec<Fragment>) -> Vec<FragmentQuad> {
+    let pixel = |fragment: &Fragment| {
+        (
+            fragment.position.get_as_sfloat32(0).floor() as i64,
+            fragment.position.get_as_sfloat32(1).floor() as i64,
+        )
+    };
+    let mut quads: Vec<[Option<Fragment>; 4]> = vec![];
+    let mut open_quads = HashMap::<(i64, i64), usize>::new();
+    for fragment in fragments {
+        let (x, y) = pixel(&fragment);
+        let lane = ((y & 1) * 2 + (x & 1)) as usize;
+        let index = match open_quads.get(&(x >> 1, y >> 1)) {
+            Some(&index) if quads[index][lane].is_none() => index,
+            _ => {
+                quads.push(Default::default());
+                open_quads.insert((x >> 1, y >> 1), quads.len() - 1);
+                quads.len() - 1
+            }
+        };
+        quads[index][lane] = Some(fragment);
+    }
+
+    quads
+        .into_iter()
+        .map(|quad| {
+            let first = quad
+                .iter()
+                .flatten()
+                .next()
+                .copied()
+                .unwrap_or_else(|| unreachable!());
+            let (x, y) = pixel(&first);
+            std::array::from_fn(|lane| {
+                quad[lane].unwrap_or_else(|| {
+                    let [_, _, z, inv_w] = first.position.get_as_f32_array();
+                    let mut helper = first;
+                    helper.position = Position::from_sfloat32_raw(
+                        (x & !1) as f32 + (lane % 2) as f32 + 0.5,
+                        (y & !1) as f32 + (lane / 2) as f32 + 0.5,
+                        z,
+                        inv_w,
+                    );
+                    helper.sample_mask = 0;
+                    helper
+                })
+            })
+        })
+        .collect()
+}
+
+/// Points are rasterized as squares of side 1 centered at vertex, coverage is determined per
+/// sample. Point size is not supported.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#primsrast-points
 pub fn draw_points(
-    vertices: impl IntoIterator<Item = Vertex>,
+    vertices: impl IntoIterator<Item = VertexShaderOutput>,
+    bounds: PixelRect,
+    samples: u32,
     fragments: &mut Vec<Fragment>,
-    color: Color,
 ) {
+    // Square is half-open towards negative axes, with single sample this selects pixel
+    // containing vertex.
+    let is_inside = |offset: f32| offset > -0.5 && offset <= 0.5;
     for vertex in vertices {
-        let position = Position::from_sfloat32(vertex.position);
-        fragments.push(Fragment { position, color });
+        let [x, y, z, inv_w] = vertex.position.get_as_f32_array();
+        for pixel_y in (y - 0.5).floor() as i64..=(y + 0.5).floor() as i64 {
+            for pixel_x in (x - 0.5).floor() as i64..=(x + 0.5).floor() as i64 {
+                if !bounds.contains(pixel_x, pixel_y) {
+                    continue;
+                }
+                let mut sample_mask = 0;
+                for (sample, &location) in sample_locations(samples).iter().enumerate() {
+                    if is_inside(pixel_x as f32 + location.0 - x)
+                        && is_inside(pixel_y as f32 + location.1 - y)
+                    {
+                        sample_mask |= 1 << sample;
+                    }
+                }
+                if sample_mask == 0 {
+                    continue;
+                }
+                fragments.push(Fragment {
+                    position: Position::from_sfloat32_raw(
+                        pixel_x as f32 + 0.5,
+                        pixel_y as f32 + 0.5,
+                        z,
+                        inv_w,
+                    ),
+                    varyings: vertex.varyings,
+                    front_facing: true,
+                    sample_mask,
+                    sample_depths: [z; MAX_SAMPLE_COUNT as usize],
+                });
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vertex(x: f32, y: f32) -> VertexShaderOutput {
+        VertexShaderOutput {
+            position: Position::from_sfloat32_raw(x, y, 0.0, 1.0),
+            ..Default::default()
+        }
+    }
+
+    fn covered(fragments: &[Fragment]) -> Vec<(u32, u32)> {
+        let mut covered = fragments
+            .iter()
+            .filter(|f| !f.is_helper_invocation())
+            .map(|f| {
+                (
+                    f.position.get_as_sfloat32(0) as u32,
+                    f.position.get_as_sfloat32(1) as u32,
+                )
+            })
+            .collect::<Vec<_>>();
+        covered.sort_unstable();
+        covered
+    }
+
+    const BOUNDS: PixelRect = PixelRect {
+        min_x: 0,
+        min_y: 0,
+        max_x: 8,
+        max_y: 8,
+    };
+
+    #[test]
+    fn triangle_covers_pixels_with_center_inside() {
+        let mut fragments = vec![];
+        draw_triangle(
+            [vertex(0.0, 0.0), vertex(6.0, 0.0), vertex(0.0, 3.0)],
+            BOUNDS,
+            1,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        assert_eq!(
+            covered(&fragments),
+            vec![
+                (0, 0),
+                (0, 1),
+                (0, 2),
+                (1, 0),
+                (1, 1),
+                (2, 0),
+                (2, 1),
+                (3, 0),
+                (4, 0)
+            ]
+        );
+    }
+
+    #[test]
+    fn triangles_sharing_edge_do_not_overlap() {
+        let mut fragments = vec![];
+        draw_triangle(
+            [vertex(0.0, 0.0), vertex(4.0, 0.0), vertex(0.0, 4.0)],
+            BOUNDS,
+            1,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        draw_triangle(
+            [vertex(4.0, 0.0), vertex(4.0, 4.0), vertex(0.0, 4.0)],
+            BOUNDS,
+            1,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        let covered = covered(&fragments);
+        assert_eq!(covered.len(), 16);
+        assert!(covered.windows(2).all(|w| w[0] != w[1]));
+    }
+
+    #[test]
+    fn triangle_split_into_tiles_covers_same_fragments() {
+        let triangle = [vertex(0.3, 0.7), vertex(7.6, 2.2), vertex(2.1, 7.9)];
+        let mut fragments = vec![];
+        draw_triangle(
+            triangle,
+            BOUNDS,
+            4,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        let mut tiled_fragments = vec![];
+        for (min_x, max_x) in [(0, 3), (3, 8)] {
+            draw_triangle(
+                triangle,
+                PixelRect {
+                    min_x,
+                    max_x,
+                    ..BOUNDS
+                },
+                4,
+                &Interpolations::default(),
+                &mut tiled_fragments,
+            );
+        }
+        assert_eq!(covered(&fragments), covered(&tiled_fragments));
+        for fragment in tiled_fragments.iter().filter(|f| !f.is_helper_invocation()) {
+            assert!(fragments.iter().any(|f| f.position == fragment.position
+                && f.sample_mask == fragment.sample_mask
+                && f.sample_depths == fragment.sample_depths));
+        }
+    }
+
+    #[test]
+    fn triangle_is_rasterized_in_quads_with_helpers() {
+        let mut fragments = vec![];
+        draw_triangle(
+            [vertex(1.0, 1.0), vertex(4.0, 1.0), vertex(1.0, 4.0)],
+            BOUNDS,
+            1,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        assert_eq!(fragments.len() % 4, 0);
+        for quad in fragments.chunks_exact(4) {
+            let (x, y) = (
+                quad[0].position.get_as_sfloat32(0),
+                quad[0].position.get_as_sfloat32(1),
+            );
+            assert_eq!((x - 0.5) % 2.0, 0.0);
+            assert_eq!((y - 0.5) % 2.0, 0.0);
+            assert_eq!(quad[3].position.get_as_sfloat32(0), x + 1.0);
+            assert_eq!(quad[3].position.get_as_sfloat32(1), y + 1.0);
+        }
+        assert!(fragments.iter().any(Fragment::is_helper_invocation));
+        // Pixel centers on hypotenuse are not covered by top-left rule.
+        assert_eq!(covered(&fragments), vec![(1, 1), (1, 2), (2, 1)]);
+    }
+
+    #[test]
+    fn only_varyings_read_by_fragment_shader_are_interpolated() {
+        let mut vertices = [vertex(0.0, 0.0), vertex(4.0, 0.0), vertex(0.0, 4.0)];
+        for vertex in &mut vertices {
+            vertex.varyings[0] = Vector4::from_sfloat32_raw(1.0, 2.0, 3.0, 4.0);
+            vertex.varyings[1] = Vector4::from_sfloat32_raw(1.0, 2.0, 3.0, 4.0);
+        }
+        let mut interpolations = Interpolations::default();
+        interpolations.inputs[1] = true;
+        let mut fragments = vec![];
+        draw_triangle(vertices, BOUNDS, 1, &interpolations, &mut fragments);
+        assert!(!fragments.is_empty());
+        for fragment in fragments {
+            assert_eq!(fragment.varyings[0], Vector4::default());
+            assert_eq!(
+                fragment.varyings[1].get_as_f32_array(),
+                [1.0, 2.0, 3.0, 4.0]
+            );
+        }
+    }
+
+    #[test]
+    fn line_fragments_are_assembled_into_quads() {
+        let mut fragments = vec![];
+        draw_line(
+            [vertex(0.5, 1.5), vertex(4.5, 1.5)],
+            1.0,
+            BOUNDS,
+            1,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        let quads = assemble_quads(fragments.clone());
+        assert_eq!(quads.len(), 2);
+        assert_eq!(
+            covered(&quads.concat()),
+            covered(&fragments),
+            "helpers must not cover samples"
+        );
+        assert_eq!(quads[1][2].position.get_as_sfloat32(0), 2.5);
+        assert_eq!(quads[1][2].position.get_as_sfloat32(1), 1.5);
+        assert!(quads[1][0].is_helper_invocation());
+    }
+
+    #[test]
+    fn multisample_coverage_on_edge() {
+        let mut fragments = vec![];
+        draw_triangle(
+            [vertex(0.0, 0.0), vertex(4.0, 0.0), vertex(0.0, 4.0)],
+            BOUNDS,
+            4,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        let fragment = fragments
+            .iter()
+            .find(|f| f.position.get_as_sfloat32(0) == 1.5 && f.position.get_as_sfloat32(1) == 2.5)
+            .unwrap_or_else(|| unreachable!());
+        assert_eq!(fragment.sample_mask, 0b0101);
+        assert!(fragments
+            .iter()
+            .any(|f| f.position.get_as_sfloat32(0) == 0.5 && f.sample_mask == 0b1111));
+    }
+
+    #[test]
+    fn line_excludes_last_fragment() {
+        let mut fragments = vec![];
+        draw_line(
+            [vertex(0.5, 1.5), vertex(4.5, 1.5)],
+            1.0,
+            BOUNDS,
+            1,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        assert_eq!(covered(&fragments), vec![(0, 1), (1, 1), (2, 1), (3, 1)]);
+
+        fragments.clear();
+        draw_line(
+            [vertex(0.5, 1.5), vertex(4.5, 1.5)],
+            3.0,
+            BOUNDS,
+            1,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        assert_eq!(fragments.len(), 12);
+    }
+
+    #[test]
+    fn varyings_are_perspective_correct() {
+        let mut v0 = vertex(0.5, 0.5);
+        let mut v1 = vertex(4.5, 0.5);
+        v1.position = Position::from_sfloat32_raw(4.5, 0.5, 0.0, 0.25);
+        v0.varyings[0] = Vector4::from_sfloat32_raw(0.0, 0.0, 0.0, 0.0);
+        v1.varyings[0] = Vector4::from_sfloat32_raw(1.0, 0.0, 0.0, 0.0);
+        let mut interpolations = Interpolations::default();
+        interpolations.inputs[0] = true;
+        let mut fragments = vec![];
+        draw_line([v0, v1], 1.0, BOUNDS, 1, &interpolations, &mut fragments);
+        let fragment = fragments[2];
+        assert_eq!(fragment.position.get_as_sfloat32(0), 2.5);
+        assert!((fragment.varyings[0].get_as_sfloat32(0) - 0.2).abs() < 1.0e-6);
+        assert!((fragment.position.get_as_sfloat32(3) - 0.625).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn flat_and_noperspective_varyings() {
+        let mut v0 = vertex(0.5, 0.5);
+        let mut v1 = vertex(4.5, 0.5);
+        v1.position = Position::from_sfloat32_raw(4.5, 0.5, 0.0, 0.25);
+        v0.varyings[0] = Vector4::from_sfloat32_raw(0.0, 0.0, 0.0, 0.0);
+        v1.varyings[0] = Vector4::from_sfloat32_raw(1.0, 0.0, 0.0, 0.0);
+        v0.varyings[1] = Vector4::from_sfloat32_raw(0.1, 0.2, 0.3, 0.4);
+        v1.varyings[1] = Vector4::from_sfloat32_raw(1.0, 1.0, 1.0, 1.0);
+        let mut interpolations = Interpolations::default();
+        interpolations.modes[0] = Interpolation::NoPerspective;
+        interpolations.modes[1] = Interpolation::Flat;
+        interpolations.inputs[..2].fill(true);
+        let mut fragments = vec![];
+        draw_line([v0, v1], 1.0, BOUNDS, 1, &interpolations, &mut fragments);
+        let fragment = fragments[2];
+        assert!((fragment.varyings[0].get_as_sfloat32(0) - 0.5).abs() < 1.0e-6);
+        assert_eq!(
+            fragment.varyings[1].get_as_f32_array(),
+            v0.varyings[1].get_as_f32_array()
+        );
+    }
+
+    fn sample_masks(fragments: &[Fragment]) -> Vec<((u32, u32), u32)> {
+        let mut masks = fragments
+            .iter()
+            .map(|f| {
+                (
+                    (
+                        f.position.get_as_sfloat32(0) as u32,
+                        f.position.get_as_sfloat32(1) as u32,
+                    ),
+                    f.sample_mask,
+                )
+            })
+            .collect::<Vec<_>>();
+        masks.sort_unstable();
+        masks
+    }
+
+    #[test]
+    fn multisampled_line_covers_rectangle() {
+        let mut fragments = vec![];
+        draw_line(
+            [vertex(1.0, 2.0), vertex(5.0, 2.0)],
+            1.0,
+            BOUNDS,
+            4,
+            &Interpolations::default(),
+            &mut fragments,
+        );
+        // Samples below and above line center are covered in adjacent rows.
+        let mut expected = (1..5)
+            .flat_map(|x| [((x, 1), 0b1100), ((x, 2), 0b0011)])
+            .collect::<Vec<_>>();
+        expected.sort_unstable();
+        assert_eq!(sample_masks(&fragments), expected);
+    }
+
+    #[test]
+    fn multisampled_point_covers_square() {
+        let mut fragments = vec![];
+        draw_points([vertex(2.0, 2.0)], BOUNDS, 4, &mut fragments);
+        assert_eq!(
+            sample_masks(&fragments),
+            vec![
+                ((1, 1), 0b1000),
+                ((1, 2), 0b0010),
+                ((2, 1), 0b0100),
+                ((2, 2), 0b0001)
+            ]
+        );
+
+        fragments.clear();
+        draw_points([vertex(2.7, 3.0)], BOUNDS, 1, &mut fragments);
+        assert_eq!(sample_masks(&fragments), vec![((2, 3), 0b1)]);
+    }
+
+    #[test]
+    fn centroid_varyings_use_covered_sample() {
+        let vertices = [vertex(0.0, 0.0), vertex(4.125, 0.0), vertex(0.0, 4.125)].map(|mut v| {
+            let [x, y, _, _] = v.position.get_as_f32_array();
+            v.varyings[0] = Vector4::from_sfloat32_raw(x, y, 0.0, 0.0);
+            v.varyings[1] = v.varyings[0];
+            v
+        });
+        let mut interpolations = Interpolations::default();
+        interpolations.centroid[1] = true;
+        interpolations.inputs[..2].fill(true);
+        let mut fragments = vec![];
+        draw_triangle(vertices, BOUNDS, 4, &interpolations, &mut fragments);
+        let at = |x: f32, y: f32| {
+            fragments
+                .iter()
+                .find(|f| f.position.get_as_sfloat32(0) == x && f.position.get_as_sfloat32(1) == y)
+                .unwrap_or_else(|| unreachable!())
+        };
+        let near = |varying: Vector4, (x, y): (f32, f32)| {
+            (varying.get_as_sfloat32(0) - x).abs() < 1.0e-4
+                && (varying.get_as_sfloat32(1) - y).abs() < 1.0e-4
+        };
+
+        // Partially covered pixel, centroid input is taken at first covered sample.
+        let partial = at(2.5, 1.5);
+        assert_eq!(partial.sample_mask, 0b0101);
+        assert!(near(partial.varyings[0], (2.5, 1.5)));
+        assert!(near(partial.varyings[1], (2.375, 1.125)));
+
+        // Fully covered pixel, centroid is pixel center.
+        let full = at(0.5, 0.5);
+        assert_eq!(full.sample_mask, 0b1111);
+        assert!(near(full.varyings[1], (0.5, 0.5)));
     }
 }
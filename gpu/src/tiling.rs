@@ -0,0 +1,280 @@
+use crate::{texel_offset, Memory};
+use common::graphics::DescriptorImage;
+use common::math::{Extent2, Format};
+use rayon::prelude::*;
+use rayon::{ThreadPool, ThreadPoolBuilder};
+use std::sync::OnceLock;
+
+/// Width and height of screen tile in pixels.
+pub const TILE_SIZE: i64 = 64;
+
+/// Environment variable overriding number of rasterizer worker threads.
+const WORKER_THREADS_VAR: &str = "ICD_WORKER_THREADS";
+
+/// Rectangle of framebuffer pixels, `min` is inclusive and `max` is exclusive.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PixelRect {
+    pub min_x: i64,
+    pub min_y: i64,
+    pub max_x: i64,
+    pub max_y: i64,
+}
+
+impl PixelRect {
+    pub const fn from_extent(extent: Extent2<u32>) -> Self {
+        Self {
+            min_x: 0,
+            min_y: 0,
+            max_x: extent.width as i64,
+            max_y: extent.height as i64,
+        }
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        let rect = Self {
+            min_x: self.min_x.max(other.min_x),
+            min_y: self.min_y.max(other.min_y),
+            max_x: self.max_x.min(other.max_x),
+            max_y: self.max_y.min(other.max_y),
+        };
+        (rect.min_x < rect.max_x && rect.min_y < rect.max_y).then_some(rect)
+    }
+
+    pub const fn contains(&self, x: i64, y: i64) -> bool {
+        x >= self.min_x && x < self.max_x && y >= self.min_y && y < self.max_y
+    }
+}
+
+/// Screen tile with indices of primitives overlapping it, in submission order.
+#[derive(Debug)]
+pub struct Tile {
+    pub rect: PixelRect,
+    pub primitives: Vec<usize>,
+}
+
+/// Grid of [`TILE_SIZE`] screen tiles covering framebuffer.
+#[derive(Debug)]
+pub struct TileGrid {
+    framebuffer: PixelRect,
+    columns: i64,
+    tiles: Vec<Tile>,
+}
+
+impl TileGrid {
+    pub fn new(framebuffer_extent: Extent2<u32>) -> Self {
+        let framebuffer = PixelRect::from_extent(framebuffer_extent);
+        let columns = (framebuffer.max_x + TILE_SIZE - 1) / TILE_SIZE;
+        let rows = (framebuffer.max_y + TILE_SIZE - 1) / TILE_SIZE;
+        let tiles = (0..rows)
+            .flat_map(|row| (0..columns).map(move |column| (row, column)))
+            .map(|(row, column)| Tile {
+                rect: PixelRect {
+                    min_x: column * TILE_SIZE,
+                    min_y: row * TILE_SIZE,
+                    max_x: ((column + 1) * TILE_SIZE).min(framebuffer.max_x),
+                    max_y: ((row + 1) * TILE_SIZE).min(framebuffer.max_y),
+                },
+                primitives: vec![],
+            })
+            .collect();
+        Self {
+            framebuffer,
+            columns,
+            tiles,
+        }
+    }
+
+    /// Adds `primitive` to all tiles overlapping its conservative `bounds`.
+    /// Primitives must be binned in submission order.
+    pub fn bin(&mut self, primitive: usize, bounds: PixelRect) {
+        let Some(bounds) = bounds.intersection(&self.framebuffer) else {
+            return;
+        };
+        for row in bounds.min_y / TILE_SIZE..=(bounds.max_y - 1) / TILE_SIZE {
+            for column in bounds.min_x / TILE_SIZE..=(bounds.max_x - 1) / TILE_SIZE {
+                self.tiles[(row * self.columns + column) as usize]
+                    .primitives
+                    .push(primitive);
+            }
+        }
+    }
+
+    /// Returns non-empty tiles in row-major order.
+    pub fn into_tiles(self) -> Vec<Tile> {
+        self.tiles
+            .into_iter()
+            .filter(|tile| !tile.primitives.is_empty())
+            .collect()
+    }
+}
+
+/// Copy of attachment texels within tile, so that tile can be processed without access to
+/// memory. Samples of texel are stored consecutively, as in image.
+#[derive(Debug)]
+pub struct TileAttachment {
+    rect: PixelRect,
+    format: Format,
+    samples: u32,
+    texels: Vec<u8>,
+}
+
+impl TileAttachment {
+    pub fn load(
+        memory: &Memory,
+        image: &DescriptorImage,
+        format: Format,
+        samples: u32,
+        rect: PixelRect,
+    ) -> Self {
+        let mut texels = vec![];
+        for y in rect.min_y..rect.max_y {
+            let offset = texel_offset(image, format, samples, rect.min_x as u64, y as u64, 0);
+            let row_size = Self::row_size(rect, format, samples);
+            texels.extend_from_slice(memory.read_bytes(&image.binding, offset, row_size));
+        }
+        Self {
+            rect,
+            format,
+            samples,
+            texels,
+        }
+    }
+
+    pub fn store(&self, memory: &mut Memory, image: &DescriptorImage) {
+        let row_size = Self::row_size(self.rect, self.format, self.samples);
+        for (y, row) in
+            (self.rect.min_y..self.rect.max_y).zip(self.texels.chunks(row_size as usize))
+        {
+            let offset = texel_offset(
+                image,
+                self.format,
+                self.samples,
+                self.rect.min_x as u64,
+                y as u64,
+                0,
+            );
+            memory.write_bytes(row, &image.binding, offset);
+        }
+    }
+
+    pub const fn format(&self) -> Format {
+        self.format
+    }
+
+    pub const fn samples(&self) -> u32 {
+        self.samples
+    }
+
+    /// Returns bytes of `sample` of texel (x, y) in framebuffer coordinates.
+    pub fn texel_mut(&mut self, x: i64, y: i64, sample: u32) -> &mut [u8] {
+        assert!(self.rect.contains(x, y));
+        let bytes_per_pixel = self.format.info().bytes_per_pixel as usize;
+        let width = (self.rect.max_x - self.rect.min_x) as usize;
+        let texel = (y - self.rect.min_y) as usize * width + (x - self.rect.min_x) as usize;
+        let offset = (texel * self.samples as usize + sample as usize) * bytes_per_pixel;
+        &mut self.texels[offset..offset + bytes_per_pixel]
+    }
+
+    const fn row_size(rect: PixelRect, format: Format, samples: u32) -> u64 {
+        (rect.max_x - rect.min_x) as u64 * samples as u64 * format.info().bytes_per_pixel as u64
+    }
+}
+
+/// Number of rasterizer worker threads, taken from `ICD_WORKER_THREADS` if set,
+/// otherwise from available parallelism.
+pub fn worker_count() -> usize {
+    static WORKER_COUNT: OnceLock<usize> = OnceLock::new();
+    *WORKER_COUNT.get_or_init(|| {
+        std::env::var(WORKER_THREADS_VAR)
+            .ok()
+            .and_then(|value| value.parse::<usize>().ok())
+            .or_else(|| std::thread::available_parallelism().ok().map(Into::into))
+            .unwrap_or(1)
+            .max(1)
+    })
+}
+
+/// Rasterizer worker threads, created once and reused by all draws.
+pub struct WorkerPool {
+    pool: ThreadPool,
+}
+
+impl WorkerPool {
+    pub fn new(threads: usize) -> Self {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads.max(1))
+            .thread_name(|index| format!("icd-worker-{index}"))
+            .build()
+            .unwrap_or_else(|error| panic!("failed to create worker threads: {error}"));
+        Self { pool }
+    }
+
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// Maps `items` with `f` on worker threads.
+    /// Results are returned in order of `items` regardless of number of threads.
+    pub fn parallel_map<T: Sync, R: Send>(
+        &self,
+        items: &[T],
+        f: impl Fn(&T) -> R + Sync + Send,
+    ) -> Vec<R> {
+        if self.threads() <= 1 || items.len() <= 1 {
+            return items.iter().map(f).collect();
+        }
+        self.pool.install(|| items.par_iter().map(f).collect())
+    }
+}
+
+impl Default for WorkerPool {
+    fn default() -> Self {
+        Self::new(worker_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bin_primitives_into_overlapping_tiles() {
+        let mut grid = TileGrid::new(Extent2 {
+            width: 2 * TILE_SIZE as u32 + 1,
+            height: TILE_SIZE as u32,
+        });
+        let bounds = |min_x, max_x| PixelRect {
+            min_x,
+            min_y: -10,
+            max_x,
+            max_y: 10,
+        };
+        grid.bin(0, bounds(0, TILE_SIZE + 1));
+        grid.bin(1, bounds(2 * TILE_SIZE, 1000));
+        grid.bin(2, bounds(-5, 1));
+        grid.bin(3, bounds(1000, 1001));
+        let tiles = grid.into_tiles();
+        assert_eq!(
+            tiles
+                .iter()
+                .map(|tile| (tile.rect.min_x, tile.rect.max_x, tile.primitives.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (0, TILE_SIZE, vec![0, 2]),
+                (TILE_SIZE, 2 * TILE_SIZE, vec![0]),
+                (2 * TILE_SIZE, 2 * TILE_SIZE + 1, vec![1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn parallel_map_preserves_order() {
+        let items = (0..1000).collect::<Vec<u32>>();
+        for threads in [1, 4] {
+            assert_eq!(
+                WorkerPool::new(threads).parallel_map(&items, |item| item * 2),
+                items.iter().map(|item| item * 2).collect::<Vec<_>>()
+            );
+        }
+    }
+}
@@ -358,11 +358,11 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_B8G8R8_SINT => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8_SRGB => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8B8A8_UNORM => Self::R8G8B8A8Unorm,
-            VkFormat::VK_FORMAT_R8G8B8A8_SNORM => unimplemented!(),
+            VkFormat::VK_FORMAT_R8G8B8A8_SNORM => Self::R8G8B8A8Snorm,
             VkFormat::VK_FORMAT_R8G8B8A8_USCALED => unimplemented!(),
             VkFormat::VK_FORMAT_R8G8B8A8_SSCALED => unimplemented!(),
-            VkFormat::VK_FORMAT_R8G8B8A8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R8G8B8A8_SINT => unimplemented!(),
+            VkFormat::VK_FORMAT_R8G8B8A8_UINT => Self::R8G8B8A8Uint,
+            VkFormat::VK_FORMAT_R8G8B8A8_SINT => Self::R8G8B8A8Sint,
             VkFormat::VK_FORMAT_R8G8B8A8_SRGB => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8A8_UNORM => unimplemented!(),
             VkFormat::VK_FORMAT_B8G8R8A8_SNORM => unimplemented!(),
@@ -420,13 +420,13 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_R16G16B16A16_SFLOAT => unimplemented!(),
             VkFormat::VK_FORMAT_R32_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_R32_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32_SFLOAT => unimplemented!(),
+            VkFormat::VK_FORMAT_R32_SFLOAT => Self::R32Sfloat,
             VkFormat::VK_FORMAT_R32G32_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_R32G32_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32_SFLOAT => unimplemented!(),
+            VkFormat::VK_FORMAT_R32G32_SFLOAT => Self::R32G32Sfloat,
             VkFormat::VK_FORMAT_R32G32B32_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_R32G32B32_SINT => unimplemented!(),
-            VkFormat::VK_FORMAT_R32G32B32_SFLOAT => unimplemented!(),
+            VkFormat::VK_FORMAT_R32G32B32_SFLOAT => Self::R32G32B32Sfloat,
             VkFormat::VK_FORMAT_R32G32B32A32_UINT => unimplemented!(),
             VkFormat::VK_FORMAT_R32G32B32A32_SINT => unimplemented!(),
             VkFormat::VK_FORMAT_R32G32B32A32_SFLOAT => Self::R32G32B32A32Sfloat,
@@ -445,12 +445,12 @@ impl From<VkFormat> for common::math::Format {
             VkFormat::VK_FORMAT_B10G11R11_UFLOAT_PACK32 => unimplemented!(),
             VkFormat::VK_FORMAT_E5B9G9R9_UFLOAT_PACK32 => unimplemented!(),
             VkFormat::VK_FORMAT_D16_UNORM => Self::D16Unorm,
-            VkFormat::VK_FORMAT_X8_D24_UNORM_PACK32 => unimplemented!(),
-            VkFormat::VK_FORMAT_D32_SFLOAT => unimplemented!(),
-            VkFormat::VK_FORMAT_S8_UINT => unimplemented!(),
+            VkFormat::VK_FORMAT_X8_D24_UNORM_PACK32 => Self::X8D24UnormPack32,
+            VkFormat::VK_FORMAT_D32_SFLOAT => Self::D32Sfloat,
+            VkFormat::VK_FORMAT_S8_UINT => Self::S8Uint,
             VkFormat::VK_FORMAT_D16_UNORM_S8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_D24_UNORM_S8_UINT => unimplemented!(),
-            VkFormat::VK_FORMAT_D32_SFLOAT_S8_UINT => unimplemented!(),
+            VkFormat::VK_FORMAT_D24_UNORM_S8_UINT => Self::D24UnormS8Uint,
+            VkFormat::VK_FORMAT_D32_SFLOAT_S8_UINT => Self::D32SfloatS8Uint,
             VkFormat::VK_FORMAT_BC1_RGB_UNORM_BLOCK => unimplemented!(),
             VkFormat::VK_FORMAT_BC1_RGB_SRGB_BLOCK => unimplemented!(),
             VkFormat::VK_FORMAT_BC1_RGBA_UNORM_BLOCK => unimplemented!(),
@@ -611,6 +611,102 @@ impl From<VkFrontFace> for common::graphics::FrontFace {
     }
 }
 
+impl From<VkCompareOp> for common::graphics::CompareOp {
+    fn from(value: VkCompareOp) -> Self {
+        match value {
+            VkCompareOp::VK_COMPARE_OP_NEVER => Self::Never,
+            VkCompareOp::VK_COMPARE_OP_LESS => Self::Less,
+            VkCompareOp::VK_COMPARE_OP_EQUAL => Self::Equal,
+            VkCompareOp::VK_COMPARE_OP_LESS_OR_EQUAL => Self::LessOrEqual,
+            VkCompareOp::VK_COMPARE_OP_GREATER => Self::Greater,
+            VkCompareOp::VK_COMPARE_OP_NOT_EQUAL => Self::NotEqual,
+            VkCompareOp::VK_COMPARE_OP_GREATER_OR_EQUAL => Self::GreaterOrEqual,
+            VkCompareOp::VK_COMPARE_OP_ALWAYS => Self::Always,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkStencilOp> for common::graphics::StencilOp {
+    fn from(value: VkStencilOp) -> Self {
+        match value {
+            VkStencilOp::VK_STENCIL_OP_KEEP => Self::Keep,
+            VkStencilOp::VK_STENCIL_OP_ZERO => Self::Zero,
+            VkStencilOp::VK_STENCIL_OP_REPLACE => Self::Replace,
+            VkStencilOp::VK_STENCIL_OP_INCREMENT_AND_CLAMP => Self::IncrementAndClamp,
+            VkStencilOp::VK_STENCIL_OP_DECREMENT_AND_CLAMP => Self::DecrementAndClamp,
+            VkStencilOp::VK_STENCIL_OP_INVERT => Self::Invert,
+            VkStencilOp::VK_STENCIL_OP_INCREMENT_AND_WRAP => Self::IncrementAndWrap,
+            VkStencilOp::VK_STENCIL_OP_DECREMENT_AND_WRAP => Self::DecrementAndWrap,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkLogicOp> for common::graphics::LogicOp {
+    fn from(value: VkLogicOp) -> Self {
+        match value {
+            VkLogicOp::VK_LOGIC_OP_CLEAR => Self::Clear,
+            VkLogicOp::VK_LOGIC_OP_AND => Self::And,
+            VkLogicOp::VK_LOGIC_OP_AND_REVERSE => Self::AndReverse,
+            VkLogicOp::VK_LOGIC_OP_COPY => Self::Copy,
+            VkLogicOp::VK_LOGIC_OP_AND_INVERTED => Self::AndInverted,
+            VkLogicOp::VK_LOGIC_OP_NO_OP => Self::NoOp,
+            VkLogicOp::VK_LOGIC_OP_XOR => Self::Xor,
+            VkLogicOp::VK_LOGIC_OP_OR => Self::Or,
+            VkLogicOp::VK_LOGIC_OP_NOR => Self::Nor,
+            VkLogicOp::VK_LOGIC_OP_EQUIVALENT => Self::Equivalent,
+            VkLogicOp::VK_LOGIC_OP_INVERT => Self::Invert,
+            VkLogicOp::VK_LOGIC_OP_OR_REVERSE => Self::OrReverse,
+            VkLogicOp::VK_LOGIC_OP_COPY_INVERTED => Self::CopyInverted,
+            VkLogicOp::VK_LOGIC_OP_OR_INVERTED => Self::OrInverted,
+            VkLogicOp::VK_LOGIC_OP_NAND => Self::Nand,
+            VkLogicOp::VK_LOGIC_OP_SET => Self::Set,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkBlendFactor> for common::graphics::BlendFactor {
+    fn from(value: VkBlendFactor) -> Self {
+        match value {
+            VkBlendFactor::VK_BLEND_FACTOR_ZERO => Self::Zero,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE => Self::One,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC_COLOR => Self::SrcColor,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC_COLOR => Self::OneMinusSrcColor,
+            VkBlendFactor::VK_BLEND_FACTOR_DST_COLOR => Self::DstColor,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_DST_COLOR => Self::OneMinusDstColor,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC_ALPHA => Self::SrcAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC_ALPHA => Self::OneMinusSrcAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_DST_ALPHA => Self::DstAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_DST_ALPHA => Self::OneMinusDstAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_CONSTANT_COLOR => Self::ConstantColor,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_CONSTANT_COLOR => Self::OneMinusConstantColor,
+            VkBlendFactor::VK_BLEND_FACTOR_CONSTANT_ALPHA => Self::ConstantAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_CONSTANT_ALPHA => Self::OneMinusConstantAlpha,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC_ALPHA_SATURATE => Self::SrcAlphaSaturate,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC1_COLOR => Self::Src1Color,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC1_COLOR => Self::OneMinusSrc1Color,
+            VkBlendFactor::VK_BLEND_FACTOR_SRC1_ALPHA => Self::Src1Alpha,
+            VkBlendFactor::VK_BLEND_FACTOR_ONE_MINUS_SRC1_ALPHA => Self::OneMinusSrc1Alpha,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<VkBlendOp> for common::graphics::BlendOp {
+    fn from(value: VkBlendOp) -> Self {
+        match value {
+            VkBlendOp::VK_BLEND_OP_ADD => Self::Add,
+            VkBlendOp::VK_BLEND_OP_SUBTRACT => Self::Subtract,
+            VkBlendOp::VK_BLEND_OP_REVERSE_SUBTRACT => Self::ReverseSubtract,
+            VkBlendOp::VK_BLEND_OP_MIN => Self::Min,
+            VkBlendOp::VK_BLEND_OP_MAX => Self::Max,
+            _ => unreachable!(),
+        }
+    }
+}
+
 impl VkIndexType {
     pub fn size_in_bytes(&self) -> u8 {
         match *self {
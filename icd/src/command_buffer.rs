@@ -366,6 +366,108 @@ pub unsafe extern "C" fn vkCmdSetScissor(
         .cmd_set_scissors(firstScissor, scissors);
 }
 
+pub unsafe extern "C" fn vkCmdSetLineWidth(commandBuffer: VkCommandBuffer, lineWidth: f32) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    commandBuffer.lock().cmd_set_line_width(lineWidth);
+}
+
+pub unsafe extern "C" fn vkCmdSetDepthBias(
+    commandBuffer: VkCommandBuffer,
+    depthBiasConstantFactor: f32,
+    depthBiasClamp: f32,
+    depthBiasSlopeFactor: f32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    commandBuffer.lock().cmd_set_depth_bias(
+        depthBiasConstantFactor,
+        depthBiasClamp,
+        depthBiasSlopeFactor,
+    );
+}
+
+pub unsafe extern "C" fn vkCmdSetBlendConstants(
+    commandBuffer: VkCommandBuffer,
+    blendConstants: *const f32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    let blend_constants = std::slice::from_raw_parts(blendConstants, 4);
+    let blend_constants = [
+        blend_constants[0],
+        blend_constants[1],
+        blend_constants[2],
+        blend_constants[3],
+    ];
+
+    commandBuffer
+        .lock()
+        .cmd_set_blend_constants(blend_constants);
+}
+
+pub unsafe extern "C" fn vkCmdSetDepthBounds(
+    commandBuffer: VkCommandBuffer,
+    minDepthBounds: f32,
+    maxDepthBounds: f32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_set_depth_bounds(minDepthBounds, maxDepthBounds);
+}
+
+pub unsafe extern "C" fn vkCmdSetStencilCompareMask(
+    commandBuffer: VkCommandBuffer,
+    faceMask: VkStencilFaceFlags,
+    compareMask: u32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_set_stencil_compare_mask(faceMask, compareMask);
+}
+
+pub unsafe extern "C" fn vkCmdSetStencilWriteMask(
+    commandBuffer: VkCommandBuffer,
+    faceMask: VkStencilFaceFlags,
+    writeMask: u32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_set_stencil_write_mask(faceMask, writeMask);
+}
+
+pub unsafe extern "C" fn vkCmdSetStencilReference(
+    commandBuffer: VkCommandBuffer,
+    faceMask: VkStencilFaceFlags,
+    reference: u32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_set_stencil_reference(faceMask, reference);
+}
+
 pub unsafe extern "C" fn vkCmdDraw(
     commandBuffer: VkCommandBuffer,
     vertexCount: u32,
@@ -403,6 +505,46 @@ pub unsafe extern "C" fn vkCmdDrawIndexed(
     );
 }
 
+pub unsafe extern "C" fn vkCmdDrawIndirect(
+    commandBuffer: VkCommandBuffer,
+    buffer: VkBuffer,
+    offset: VkDeviceSize,
+    drawCount: u32,
+    stride: u32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    let Some(buffer) = Buffer::from_handle(buffer) else {
+        unreachable!()
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_draw_indirect(buffer, offset, drawCount, stride);
+}
+
+pub unsafe extern "C" fn vkCmdDrawIndexedIndirect(
+    commandBuffer: VkCommandBuffer,
+    buffer: VkBuffer,
+    offset: VkDeviceSize,
+    drawCount: u32,
+    stride: u32,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    let Some(buffer) = Buffer::from_handle(buffer) else {
+        unreachable!()
+    };
+
+    commandBuffer
+        .lock()
+        .cmd_draw_indexed_indirect(buffer, offset, drawCount, stride);
+}
+
 pub unsafe extern "C" fn vkCmdCopyBufferToImage(
     commandBuffer: VkCommandBuffer,
     srcBuffer: VkBuffer,
@@ -461,6 +603,40 @@ pub unsafe extern "C" fn vkCmdCopyImageToBuffer(
         .cmd_copy_image_to_buffer(srcImage, dstBuffer, srcImageLayout, regions);
 }
 
+pub unsafe extern "C" fn vkCmdResolveImage(
+    commandBuffer: VkCommandBuffer,
+    srcImage: VkImage,
+    srcImageLayout: VkImageLayout,
+    dstImage: VkImage,
+    dstImageLayout: VkImageLayout,
+    regionCount: u32,
+    pRegions: Option<NonNull<VkImageResolve>>,
+) {
+    let Some(commandBuffer) = CommandBuffer::from_handle(commandBuffer) else {
+        unreachable!()
+    };
+
+    let Some(srcImage) = Image::from_handle(srcImage) else {
+        unreachable!()
+    };
+
+    let Some(dstImage) = Image::from_handle(dstImage) else {
+        unreachable!()
+    };
+
+    let regions = pRegions.map_or(&[] as &[_], |x| {
+        std::slice::from_raw_parts(x.as_ptr(), regionCount as usize)
+    });
+
+    commandBuffer.lock().cmd_resolve_image(
+        srcImage,
+        srcImageLayout,
+        dstImage,
+        dstImageLayout,
+        regions,
+    );
+}
+
 pub unsafe extern "C" fn vkCmdCopyBuffer(
     commandBuffer: VkCommandBuffer,
     srcBuffer: VkBuffer,
@@ -71,6 +71,7 @@ pub unsafe extern "C" fn vkCreateImage(
         create_info.extent.width,
         create_info.extent.height,
         create_info.arrayLayers,
+        create_info.samples,
         create_info.usage,
     );
 
@@ -1038,28 +1038,6 @@ pub unsafe extern "C" fn vkBindVideoSessionMemoryKHR(
     )
 }
 
-pub unsafe extern "C" fn vkCmdResolveImage(
-    commandBuffer: VkCommandBuffer,
-    srcImage: VkImage,
-    srcImageLayout: VkImageLayout,
-    dstImage: VkImage,
-    dstImageLayout: VkImageLayout,
-    regionCount: u32,
-    pRegions: Option<NonNull<VkImageResolve>>,
-) {
-    unimplemented!(
-        "vkCmdResolveImage(
-        commandBuffer,
-        srcImage,
-        srcImageLayout,
-        dstImage,
-        dstImageLayout,
-        regionCount,
-        pRegions,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdSetProvokingVertexModeEXT(
     commandBuffer: VkCommandBuffer,
     provokingVertexMode: VkProvokingVertexModeEXT,
@@ -1570,16 +1548,6 @@ pub unsafe extern "C" fn vkSignalSemaphore(
     unimplemented!("vkSignalSemaphore(device, pSignalInfo")
 }
 
-pub unsafe extern "C" fn vkCmdDrawIndexedIndirect(
-    commandBuffer: VkCommandBuffer,
-    buffer: VkBuffer,
-    offset: VkDeviceSize,
-    drawCount: u32,
-    stride: u32,
-) {
-    unimplemented!("vkCmdDrawIndexedIndirect(commandBuffer, buffer, offset, drawCount, stride")
-}
-
 pub unsafe extern "C" fn vkCmdSetRasterizerDiscardEnable(
     commandBuffer: VkCommandBuffer,
     rasterizerDiscardEnable: VkBool32,
@@ -1664,14 +1632,6 @@ pub unsafe extern "C" fn vkSetEvent(device: VkDevice, event: VkEvent) -> VkResul
     unimplemented!("vkSetEvent(device, event")
 }
 
-pub unsafe extern "C" fn vkCmdSetStencilReference(
-    commandBuffer: VkCommandBuffer,
-    faceMask: VkStencilFaceFlags,
-    reference: u32,
-) {
-    unimplemented!("vkCmdSetStencilReference(commandBuffer, faceMask, reference")
-}
-
 pub unsafe extern "C" fn vkGetPhysicalDeviceMemoryProperties2(
     physicalDevice: VkPhysicalDevice,
     pMemoryProperties: Option<NonNull<VkPhysicalDeviceMemoryProperties2>>,
@@ -1740,22 +1700,6 @@ pub unsafe extern "C" fn vkCreateSemaphoreSciSyncPoolNV(
     unimplemented!("vkCreateSemaphoreSciSyncPoolNV(device, pCreateInfo, pAllocator, pSemaphorePool")
 }
 
-pub unsafe extern "C" fn vkCmdSetDepthBias(
-    commandBuffer: VkCommandBuffer,
-    depthBiasConstantFactor: f32,
-    depthBiasClamp: f32,
-    depthBiasSlopeFactor: f32,
-) {
-    unimplemented!(
-        "vkCmdSetDepthBias(
-        commandBuffer,
-        depthBiasConstantFactor,
-        depthBiasClamp,
-        depthBiasSlopeFactor,
-    "
-    )
-}
-
 pub unsafe extern "C" fn vkCmdDrawClusterIndirectHUAWEI(
     commandBuffer: VkCommandBuffer,
     buffer: VkBuffer,
@@ -3007,16 +2951,6 @@ pub unsafe extern "C" fn vkSetPrivateData(
     unimplemented!("vkSetPrivateData(device, objectType, objectHandle, privateDataSlot, data")
 }
 
-pub unsafe extern "C" fn vkCmdDrawIndirect(
-    commandBuffer: VkCommandBuffer,
-    buffer: VkBuffer,
-    offset: VkDeviceSize,
-    drawCount: u32,
-    stride: u32,
-) {
-    unimplemented!("vkCmdDrawIndirect(commandBuffer, buffer, offset, drawCount, stride")
-}
-
 pub unsafe extern "C" fn vkGetImageSparseMemoryRequirements2(
     device: VkDevice,
     pInfo: Option<NonNull<VkImageSparseMemoryRequirementsInfo2>>,
@@ -3082,14 +3016,6 @@ pub unsafe extern "C" fn vkCreateDirectFBSurfaceEXT(
     unimplemented!("vkCreateDirectFBSurfaceEXT(instance, pCreateInfo, pAllocator, pSurface")
 }
 
-pub unsafe extern "C" fn vkCmdSetStencilWriteMask(
-    commandBuffer: VkCommandBuffer,
-    faceMask: VkStencilFaceFlags,
-    writeMask: u32,
-) {
-    unimplemented!("vkCmdSetStencilWriteMask(commandBuffer, faceMask, writeMask")
-}
-
 pub unsafe extern "C" fn vkCreateImagePipeSurfaceFUCHSIA(
     instance: VkInstance,
     pCreateInfo: Option<NonNull<VkImagePipeSurfaceCreateInfoFUCHSIA>>,
@@ -3909,14 +3835,6 @@ pub unsafe extern "C" fn vkCmdFillBuffer(
     unimplemented!("vkCmdFillBuffer(commandBuffer, dstBuffer, dstOffset, size, data")
 }
 
-pub unsafe extern "C" fn vkCmdSetBlendConstants(
-    commandBuffer: VkCommandBuffer,
-    blendConstants: *const f32,
-) {
-    let _ = unsafe { std::slice::from_raw_parts(blendConstants, 4) };
-    unimplemented!("vkCmdSetBlendConstants(commandBuffer, blendConstants")
-}
-
 pub unsafe extern "C" fn vkGetPhysicalDeviceImageFormatProperties2(
     physicalDevice: VkPhysicalDevice,
     pImageFormatInfo: Option<NonNull<VkPhysicalDeviceImageFormatInfo2>>,
@@ -4295,10 +4213,6 @@ pub unsafe extern "C" fn vkGetSwapchainGrallocUsage2ANDROID(
     )
 }
 
-pub unsafe extern "C" fn vkCmdSetLineWidth(commandBuffer: VkCommandBuffer, lineWidth: f32) {
-    unimplemented!("vkCmdSetLineWidth(commandBuffer, lineWidth")
-}
-
 pub unsafe extern "C" fn vkCmdDebugMarkerBeginEXT(
     commandBuffer: VkCommandBuffer,
     pMarkerInfo: Option<NonNull<VkDebugMarkerMarkerInfoEXT>>,
@@ -4462,14 +4376,6 @@ pub unsafe extern "C" fn vkGetFenceSciSyncObjNV(
     unimplemented!("vkGetFenceSciSyncObjNV(device, pGetSciSyncHandleInfo, pHandle")
 }
 
-pub unsafe extern "C" fn vkCmdSetStencilCompareMask(
-    commandBuffer: VkCommandBuffer,
-    faceMask: VkStencilFaceFlags,
-    compareMask: u32,
-) {
-    unimplemented!("vkCmdSetStencilCompareMask(commandBuffer, faceMask, compareMask")
-}
-
 pub unsafe extern "C" fn vkBindOpticalFlowSessionImageNV(
     device: VkDevice,
     session: VkOpticalFlowSessionNV,
@@ -4644,14 +4550,6 @@ pub unsafe extern "C" fn vkCmdSetLogicOpEnableEXT(
     unimplemented!("vkCmdSetLogicOpEnableEXT(commandBuffer, logicOpEnable")
 }
 
-pub unsafe extern "C" fn vkCmdSetDepthBounds(
-    commandBuffer: VkCommandBuffer,
-    minDepthBounds: f32,
-    maxDepthBounds: f32,
-) {
-    unimplemented!("vkCmdSetDepthBounds(commandBuffer, minDepthBounds, maxDepthBounds")
-}
-
 pub unsafe extern "C" fn vkMapMemory2KHR(
     device: VkDevice,
     pMemoryMapInfo: Option<NonNull<VkMemoryMapInfoKHR>>,
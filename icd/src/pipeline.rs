@@ -288,33 +288,37 @@ pub unsafe extern "C" fn vkCreateGraphicsPipelines(
                 continue;
             }
         };
-        let vertex_input_state = create_info
-            .pVertexInputState
-            .map(|x| PhysicalDevice::parse_vertex_input_state(*x.as_ref()));
-        let input_assembly_state = create_info
-            .pInputAssemblyState
-            .map(|x| PhysicalDevice::parse_input_assembly_state(*x.as_ref()));
         warn!("TODO: Parse rest of Vulkan pipeline states");
-        let _tessellation_state = create_info.pTessellationState.map(|x| x.as_ref());
-        let viewport_state = create_info
-            .pViewportState
-            .map(|x| PhysicalDevice::parse_viewport_state(*x.as_ref()));
-        let rasterization_state = create_info
-            .pRasterizationState
-            .map(|x| PhysicalDevice::parse_rasterization_state(*x.as_ref()));
-        let _multisample_state = create_info.pMultisampleState.map(|x| x.as_ref());
-        let _depth_stencil_state = create_info.pDepthStencilState.map(|x| x.as_ref());
-        let _color_blend_state = create_info.pColorBlendState.map(|x| x.as_ref());
-        let _dynamic_state = create_info.pDynamicState.map(|x| x.as_ref());
-        *pipeline = Pipeline::create(
-            device.clone(),
-            pipelineCache.clone(),
-            shader_state,
-            vertex_input_state,
-            input_assembly_state,
-            viewport_state,
-            rasterization_state,
-        );
+        let states = GraphicsPipelineStates {
+            vertex_input_state: create_info
+                .pVertexInputState
+                .map(|x| PhysicalDevice::parse_vertex_input_state(*x.as_ref())),
+            input_assembly_state: create_info
+                .pInputAssemblyState
+                .map(|x| PhysicalDevice::parse_input_assembly_state(*x.as_ref())),
+            tessellation_state: create_info
+                .pTessellationState
+                .map(|x| PhysicalDevice::parse_tessellation_state(*x.as_ref())),
+            viewport_state: create_info
+                .pViewportState
+                .map(|x| PhysicalDevice::parse_viewport_state(*x.as_ref())),
+            rasterization_state: create_info
+                .pRasterizationState
+                .map(|x| PhysicalDevice::parse_rasterization_state(*x.as_ref())),
+            multisample_state: create_info
+                .pMultisampleState
+                .map(|x| PhysicalDevice::parse_multisample_state(*x.as_ref())),
+            depth_stencil_state: create_info
+                .pDepthStencilState
+                .map(|x| PhysicalDevice::parse_depth_stencil_state(*x.as_ref())),
+            color_blend_state: create_info
+                .pColorBlendState
+                .map(|x| PhysicalDevice::parse_color_blend_state(*x.as_ref())),
+            dynamic_states: create_info
+                .pDynamicState
+                .map(|x| PhysicalDevice::parse_dynamic_state(*x.as_ref())),
+        };
+        *pipeline = Pipeline::create(device.clone(), pipelineCache.clone(), shader_state, states);
     }
 
     result
@@ -4,10 +4,11 @@ use crate::buffer::Buffer;
 use crate::context::{Dispatchable, NonDispatchable};
 use crate::image::Image;
 use crate::logical_device::LogicalDevice;
+use crate::physical_device::PhysicalDevice;
 use crate::pipeline::{Framebuffer, Pipeline, PipelineLayout, RenderPass};
 use common::graphics::{IndexBuffer, VertexBindingNumber, VertexBuffer};
-use common::math::{Extent2, Extent3, Offset2, Offset3};
-use gpu::{Command, RegionCopyBufferImage};
+use common::math::{Extent2, Extent3, Format, Offset2, Offset3, Range2};
+use gpu::{Command, RegionCopyBufferImage, RegionResolveImage};
 use headers::vk_decls::*;
 use itertools::izip;
 use log::*;
@@ -16,7 +17,6 @@ use std::fmt::Debug;
 use std::sync::Arc;
 
 #[derive(Debug)]
-
 #[allow(dead_code)]
 pub struct CommandPool {
     pub(crate) handle: VkNonDispatchableHandle,
@@ -53,6 +53,8 @@ pub struct CommandBuffer {
     command_pool: Arc<Mutex<CommandPool>>,
     gpu_command_buffer: gpu::CommandBuffer,
     gpu_bound_render_target_indices: Vec<gpu::RenderTargetIndex>,
+    gpu_bound_depth_stencil_target: bool,
+    gpu_subpass_resolves: Vec<Command>,
 }
 
 impl CommandBuffer {
@@ -70,6 +72,8 @@ impl CommandBuffer {
             command_pool,
             gpu_command_buffer: gpu::CommandBuffer::new(),
             gpu_bound_render_target_indices: vec![],
+            gpu_bound_depth_stencil_target: false,
+            gpu_subpass_resolves: vec![],
         };
         object.register_object()
     }
@@ -100,9 +104,15 @@ impl CommandBuffer {
     ) {
         let render_pass = render_pass.lock();
         let descriptions = render_pass.attachments.clone();
+        warn!("TODO: Support multiple subpasses");
+        let subpass = render_pass.subpasses[0].clone();
         drop(render_pass);
         let framebuffer = framebuffer.lock();
         let image_views = framebuffer.attachments.clone();
+        let framebuffer_extent = Extent2::<u32> {
+            width: framebuffer.width,
+            height: framebuffer.height,
+        };
         drop(framebuffer);
         let _ = contents;
 
@@ -118,31 +128,135 @@ impl CommandBuffer {
         };
 
         assert!(self.gpu_bound_render_target_indices.is_empty());
-        izip!(descriptions.iter(), image_views.iter(), clear_values.iter())
-            .enumerate()
-            .for_each(|(index, (description, image_view, clear_value))| {
-                let index = gpu::RenderTargetIndex(index);
-                self.gpu_bound_render_target_indices.push(index);
-
-                self.gpu_command_buffer.record(Command::BindRenderTarget {
-                    render_target: gpu::RenderTarget {
-                        index,
-                        format: description.format.into(),
-                        samples: description.samples.into(),
-                        image: image_view.lock().image.lock().descriptor(),
+        assert!(!self.gpu_bound_depth_stencil_target);
+        assert!(self.gpu_subpass_resolves.is_empty());
+
+        self.gpu_command_buffer
+            .record(Command::SetFramebufferExtent {
+                extent: framebuffer_extent,
+            });
+
+        // Multisampled color attachments are resolved at the end of subpass.
+        for (color, resolve) in subpass
+            .color_attachments
+            .iter()
+            .zip(subpass.resolve_attachments.iter())
+        {
+            if color.attachment == VK_ATTACHMENT_UNUSED
+                || resolve.attachment == VK_ATTACHMENT_UNUSED
+            {
+                continue;
+            }
+            let description = &descriptions[color.attachment as usize];
+            let image = |attachment: u32| {
+                image_views[attachment as usize]
+                    .lock()
+                    .image
+                    .lock()
+                    .descriptor()
+            };
+            let offset = Offset3::<i32> {
+                x: render_area.offset.x,
+                y: render_area.offset.y,
+                z: 0,
+            };
+            self.gpu_subpass_resolves.push(Command::ResolveImage {
+                src_image: image(color.attachment),
+                dst_image: image(resolve.attachment),
+                region: RegionResolveImage {
+                    src_offset: offset,
+                    dst_offset: offset,
+                    extent: Extent3::<u32> {
+                        width: render_area.extent.width,
+                        height: render_area.extent.height,
+                        depth: 1,
                     },
-                });
+                    format: description.format.into(),
+                    samples: description.samples.into(),
+                },
+            });
+        }
+        izip!(descriptions.iter(), image_views.iter())
+            .enumerate()
+            .for_each(|(attachment, (description, image_view))| {
+                let image = image_view.lock().image.lock().descriptor();
+                let format = Format::from(description.format);
+                let samples = description.samples.into();
+                let is_depth_stencil = subpass
+                    .depth_stencil_attachment
+                    .is_some_and(|x| x.attachment == attachment as u32);
+                // Color attachments are bound by their location in subpass.
+                let index = if is_depth_stencil {
+                    self.gpu_bound_depth_stencil_target = true;
+                    self.gpu_command_buffer
+                        .record(Command::BindDepthStencilTarget {
+                            depth_stencil_target: gpu::DepthStencilTarget {
+                                format,
+                                samples,
+                                image: image.clone(),
+                            },
+                        });
+                    None
+                } else if let Some(location) = subpass
+                    .color_attachments
+                    .iter()
+                    .position(|x| x.attachment == attachment as u32)
+                {
+                    let index = gpu::RenderTargetIndex(location);
+                    self.gpu_bound_render_target_indices.push(index);
+                    self.gpu_command_buffer.record(Command::BindRenderTarget {
+                        render_target: gpu::RenderTarget {
+                            index,
+                            format,
+                            samples,
+                            image: image.clone(),
+                        },
+                    });
+                    Some(index)
+                } else {
+                    // NOTE: Load operations still apply to resolve and input attachments.
+                    warn!("TODO: Bind input attachments");
+                    None
+                };
+                let has_depth_stencil = format.has_depth() || format.has_stencil();
 
                 match description.load_op {
                     VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_LOAD => {
                         // No-op.
                     }
                     VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR => {
-                        self.gpu_command_buffer.record(Command::ClearRenderTarget {
-                            index,
-                            render_area,
-                            color: (*clear_value).into(),
-                        });
+                        let Some(clear_value) = clear_values.get(attachment) else {
+                            unreachable!()
+                        };
+                        let depth = Some(unsafe { clear_value.depthStencil.depth });
+                        let command = match (index, is_depth_stencil, has_depth_stencil) {
+                            (Some(index), _, _) => Command::ClearRenderTarget {
+                                index,
+                                render_area,
+                                color: (*clear_value).into(),
+                            },
+                            (None, true, _) => Command::ClearDepthStencilTarget {
+                                render_area,
+                                depth,
+                                stencil: None,
+                            },
+                            (None, false, true) => Command::ClearDepthStencilImage {
+                                image: image.clone(),
+                                format,
+                                samples,
+                                render_area,
+                                depth,
+                                stencil: None,
+                            },
+                            (None, false, false) => Command::ClearColorImage {
+                                image: image.clone(),
+                                format,
+                                samples,
+                                render_area,
+                                color: (*clear_value).into(),
+                            },
+                        };
+                        self.gpu_command_buffer.record(command);
                     }
                     VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE
                     | VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_NONE_EXT => {
@@ -162,12 +276,36 @@ impl CommandBuffer {
                     _ => unreachable!(),
                 };
 
+                // Stencil load and store operations apply only to depth/stencil attachment.
+                if !has_depth_stencil {
+                    return;
+                }
+
                 match description.stencil_load_pp {
                     VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_LOAD => {
-                        warn!("TODO: Stencil commands support");
+                        // No-op.
                     }
                     VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_CLEAR => {
-                        warn!("TODO: Stencil commands support");
+                        let Some(clear_value) = clear_values.get(attachment) else {
+                            unreachable!()
+                        };
+                        let stencil = Some(unsafe { clear_value.depthStencil.stencil });
+                        self.gpu_command_buffer.record(if is_depth_stencil {
+                            Command::ClearDepthStencilTarget {
+                                render_area,
+                                depth: None,
+                                stencil,
+                            }
+                        } else {
+                            Command::ClearDepthStencilImage {
+                                image,
+                                format,
+                                samples,
+                                render_area,
+                                depth: None,
+                                stencil,
+                            }
+                        });
                     }
                     VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_DONT_CARE
                     | VkAttachmentLoadOp::VK_ATTACHMENT_LOAD_OP_NONE_EXT => {
@@ -178,7 +316,7 @@ impl CommandBuffer {
 
                 match description.stencil_store_op {
                     VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_STORE => {
-                        warn!("TODO: Stencil commands support");
+                        // No-op.
                     }
                     VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_DONT_CARE
                     | VkAttachmentStoreOp::VK_ATTACHMENT_STORE_OP_NONE => {
@@ -190,10 +328,17 @@ impl CommandBuffer {
     }
 
     pub fn cmd_end_render_pass(&mut self) {
+        for command in self.gpu_subpass_resolves.drain(..) {
+            self.gpu_command_buffer.record(command);
+        }
         for index in self.gpu_bound_render_target_indices.drain(..) {
             self.gpu_command_buffer
                 .record(Command::UnbindRenderTarget { index });
         }
+        if std::mem::take(&mut self.gpu_bound_depth_stencil_target) {
+            self.gpu_command_buffer
+                .record(Command::UnbindDepthStencilTarget);
+        }
     }
 
     pub fn cmd_bind_pipeline(
@@ -272,16 +417,76 @@ impl CommandBuffer {
 
     pub fn cmd_set_viewport(&mut self, first_viewport: u32, viewports: &[VkViewport]) {
         trace!("CommandBuffer::cmd_set_viewport");
-        let _ = first_viewport;
-        let _ = viewports;
-        // TODO: Record viewport dynamic state change.
+        self.gpu_command_buffer.record(Command::SetViewports {
+            first_viewport,
+            viewports: viewports
+                .iter()
+                .map(PhysicalDevice::parse_viewport)
+                .collect(),
+        });
     }
 
     pub fn cmd_set_scissors(&mut self, first_scissor: u32, scissors: &[VkRect2D]) {
         trace!("CommandBuffer::cmd_set_scissors");
-        let _ = first_scissor;
-        let _ = scissors;
-        // TODO: Record scissors dynamic state change.
+        self.gpu_command_buffer.record(Command::SetScissors {
+            first_scissor,
+            scissors: scissors.iter().map(PhysicalDevice::parse_scissor).collect(),
+        });
+    }
+
+    pub fn cmd_set_line_width(&mut self, line_width: f32) {
+        self.gpu_command_buffer
+            .record(Command::SetLineWidth { line_width });
+    }
+
+    pub fn cmd_set_depth_bias(&mut self, constant_factor: f32, clamp: f32, slope_factor: f32) {
+        self.gpu_command_buffer.record(Command::SetDepthBias {
+            constant_factor,
+            clamp,
+            slope_factor,
+        });
+    }
+
+    pub fn cmd_set_blend_constants(&mut self, blend_constants: [f32; 4]) {
+        self.gpu_command_buffer
+            .record(Command::SetBlendConstants { blend_constants });
+    }
+
+    pub fn cmd_set_depth_bounds(&mut self, min_depth_bounds: f32, max_depth_bounds: f32) {
+        self.gpu_command_buffer.record(Command::SetDepthBounds {
+            depth_bounds: Range2 {
+                min: min_depth_bounds,
+                max: max_depth_bounds,
+            },
+        });
+    }
+
+    pub fn cmd_set_stencil_compare_mask(
+        &mut self,
+        face_mask: VkStencilFaceFlags,
+        compare_mask: u32,
+    ) {
+        self.gpu_command_buffer
+            .record(Command::SetStencilCompareMask {
+                faces: PhysicalDevice::parse_stencil_faces(face_mask),
+                compare_mask,
+            });
+    }
+
+    pub fn cmd_set_stencil_write_mask(&mut self, face_mask: VkStencilFaceFlags, write_mask: u32) {
+        self.gpu_command_buffer
+            .record(Command::SetStencilWriteMask {
+                faces: PhysicalDevice::parse_stencil_faces(face_mask),
+                write_mask,
+            });
+    }
+
+    pub fn cmd_set_stencil_reference(&mut self, face_mask: VkStencilFaceFlags, reference: u32) {
+        self.gpu_command_buffer
+            .record(Command::SetStencilReference {
+                faces: PhysicalDevice::parse_stencil_faces(face_mask),
+                reference,
+            });
     }
 
     pub fn cmd_draw(
@@ -317,6 +522,38 @@ impl CommandBuffer {
             });
     }
 
+    pub fn cmd_draw_indirect(
+        &mut self,
+        buffer: Arc<Mutex<Buffer>>,
+        offset: VkDeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        self.gpu_command_buffer
+            .record(Command::DrawPrimitiveIndirect {
+                buffer: buffer.lock().descriptor(),
+                offset,
+                draw_count,
+                stride,
+            });
+    }
+
+    pub fn cmd_draw_indexed_indirect(
+        &mut self,
+        buffer: Arc<Mutex<Buffer>>,
+        offset: VkDeviceSize,
+        draw_count: u32,
+        stride: u32,
+    ) {
+        self.gpu_command_buffer
+            .record(Command::DrawPrimitiveIndexedIndirect {
+                buffer: buffer.lock().descriptor(),
+                offset,
+                draw_count,
+                stride,
+            });
+    }
+
     pub fn cmd_copy_buffer_to_image(
         &mut self,
         src_buffer: Arc<Mutex<Buffer>>,
@@ -391,6 +628,50 @@ impl CommandBuffer {
         }
     }
 
+    pub fn cmd_resolve_image(
+        &mut self,
+        src_image: Arc<Mutex<Image>>,
+        src_image_layout: VkImageLayout,
+        dst_image: Arc<Mutex<Image>>,
+        dst_image_layout: VkImageLayout,
+        regions: &[VkImageResolve],
+    ) {
+        let _ = src_image_layout;
+        let _ = dst_image_layout;
+        let src_image = src_image.lock();
+        let dst_image = dst_image.lock();
+        for region in regions {
+            assert_eq!(region.srcSubresource.mipLevel, 0);
+            assert_eq!(region.dstSubresource.mipLevel, 0);
+            assert_eq!(region.srcSubresource.baseArrayLayer, 0);
+            assert_eq!(region.dstSubresource.baseArrayLayer, 0);
+            assert_eq!(region.srcSubresource.layerCount, 1);
+            self.gpu_command_buffer.record(Command::ResolveImage {
+                src_image: src_image.descriptor(),
+                dst_image: dst_image.descriptor(),
+                region: RegionResolveImage {
+                    src_offset: Offset3::<i32> {
+                        x: region.srcOffset.x,
+                        y: region.srcOffset.y,
+                        z: region.srcOffset.z,
+                    },
+                    dst_offset: Offset3::<i32> {
+                        x: region.dstOffset.x,
+                        y: region.dstOffset.y,
+                        z: region.dstOffset.z,
+                    },
+                    extent: Extent3::<u32> {
+                        width: region.extent.width,
+                        height: region.extent.height,
+                        depth: region.extent.depth,
+                    },
+                    format: src_image.format.into(),
+                    samples: src_image.samples,
+                },
+            })
+        }
+    }
+
     pub fn cmd_copy_buffer_to_buffer(
         &mut self,
         src_buffer: Arc<Mutex<Buffer>>,
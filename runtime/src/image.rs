@@ -19,6 +19,7 @@ pub struct Image {
     pub(crate) format: VkFormat,
     width: u32,
     height: u32,
+    pub(crate) samples: u32,
     gpu_binding: MemoryBinding,
 }
 
@@ -29,6 +30,7 @@ impl Image {
         width: u32,
         height: u32,
         array_layers: u32,
+        samples: VkSampleCountFlagBits,
         image_usage: VkImageUsageFlags,
     ) -> VkNonDispatchableHandle {
         info!("new Image");
@@ -43,13 +45,17 @@ impl Image {
             format,
             width,
             height,
+            samples: samples.into(),
             gpu_binding: Default::default(),
         };
         image.register_object()
     }
 
     pub const fn size_in_bytes(&self) -> u64 {
-        self.width as u64 * self.height as u64 * self.format.bytes_per_pixel() as u64
+        self.width as u64
+            * self.height as u64
+            * self.samples as u64
+            * self.format.bytes_per_pixel() as u64
     }
 
     pub fn memory_requirements(&self) -> VkMemoryRequirements {
@@ -3,16 +3,19 @@
 use crate::context::{Dispatchable, NonDispatchable};
 use crate::pipeline::ShaderModule;
 use common::consts::{
-    MAX_VERTEX_ATTRIBUTES, MAX_VERTEX_ATTRIBUTE_OFFSET, MAX_VERTEX_BINDINGS,
-    MAX_VERTEX_BINDING_STRIDE, MAX_VIEWPORTS, MAX_VIEWPORT_DIMENSIONS, VIEWPORT_BOUNDS_RANGE,
+    LINE_WIDTH_GRANULARITY, LINE_WIDTH_RANGE, MAX_COLOR_ATTACHMENTS, MAX_DRAW_INDIRECT_COUNT,
+    MAX_FRAGMENT_INPUT_COMPONENTS, MAX_VERTEX_ATTRIBUTES, MAX_VERTEX_ATTRIBUTE_OFFSET,
+    MAX_VERTEX_BINDINGS, MAX_VERTEX_BINDING_STRIDE, MAX_VERTEX_OUTPUT_COMPONENTS, MAX_VIEWPORTS,
+    MAX_VIEWPORT_DIMENSIONS, VIEWPORT_BOUNDS_RANGE,
 };
 use common::graphics::{
     VertexAttribute, VertexBinding, VertexBindingNumber, VertexInputRate, VertexInputState,
 };
 use common::math::{Extent2, Offset2, Range2};
 use gpu::{
-    InputAssemblyState, PrimitiveTopology, RasterizationState, RenderArea, Scissor, Viewport,
-    ViewportState,
+    ColorBlendAttachmentState, ColorBlendState, DepthStencilState, DynamicStates,
+    InputAssemblyState, MultisampleState, PrimitiveTopology, RasterizationState, RenderArea,
+    Scissor, StencilFaces, StencilOpState, TessellationState, Viewport, ViewportState,
 };
 use headers::c_char_array;
 use headers::vk_decls::*;
@@ -84,6 +87,16 @@ impl PhysicalDevice {
         ]
     }
 
+    /// Supported multisample counts of framebuffer attachments.
+    fn sample_counts() -> VkSampleCountFlags {
+        VkSampleCountFlags::from(
+            VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT
+                | VkSampleCountFlagBits::VK_SAMPLE_COUNT_2_BIT
+                | VkSampleCountFlagBits::VK_SAMPLE_COUNT_4_BIT
+                | VkSampleCountFlagBits::VK_SAMPLE_COUNT_8_BIT,
+        )
+    }
+
     pub fn properties(&self) -> VkPhysicalDeviceProperties {
         c_char_array!(
             DEVICE_NAME,
@@ -136,7 +149,7 @@ impl PhysicalDevice {
                 maxVertexInputBindings: MAX_VERTEX_BINDINGS,
                 maxVertexInputAttributeOffset: MAX_VERTEX_ATTRIBUTE_OFFSET,
                 maxVertexInputBindingStride: MAX_VERTEX_BINDING_STRIDE,
-                maxVertexOutputComponents: 0,
+                maxVertexOutputComponents: MAX_VERTEX_OUTPUT_COMPONENTS,
                 maxTessellationGenerationLevel: 0,
                 maxTessellationPatchSize: 0,
                 maxTessellationControlPerVertexInputComponents: 0,
@@ -150,10 +163,10 @@ impl PhysicalDevice {
                 maxGeometryOutputComponents: 0,
                 maxGeometryOutputVertices: 0,
                 maxGeometryTotalOutputComponents: 0,
-                maxFragmentInputComponents: 0,
-                maxFragmentOutputAttachments: 0,
+                maxFragmentInputComponents: MAX_FRAGMENT_INPUT_COMPONENTS,
+                maxFragmentOutputAttachments: MAX_COLOR_ATTACHMENTS,
                 maxFragmentDualSrcAttachments: 0,
-                maxFragmentCombinedOutputResources: 0,
+                maxFragmentCombinedOutputResources: MAX_COLOR_ATTACHMENTS,
                 maxComputeSharedMemorySize: 0,
                 maxComputeWorkGroupCount: [0, 0, 0],
                 maxComputeWorkGroupInvocations: 0,
@@ -162,7 +175,7 @@ impl PhysicalDevice {
                 subTexelPrecisionBits: 0,
                 mipmapPrecisionBits: 0,
                 maxDrawIndexedIndexValue: 0,
-                maxDrawIndirectCount: 0,
+                maxDrawIndirectCount: MAX_DRAW_INDIRECT_COUNT,
                 maxSamplerLodBias: 0.0,
                 maxSamplerAnisotropy: 0.0,
                 maxViewports: MAX_VIEWPORTS,
@@ -183,17 +196,17 @@ impl PhysicalDevice {
                 maxFramebufferWidth: 0,
                 maxFramebufferHeight: 0,
                 maxFramebufferLayers: 0,
-                framebufferColorSampleCounts: 0,
-                framebufferDepthSampleCounts: 0,
-                framebufferStencilSampleCounts: 0,
-                framebufferNoAttachmentsSampleCounts: 0,
-                maxColorAttachments: 0,
+                framebufferColorSampleCounts: Self::sample_counts(),
+                framebufferDepthSampleCounts: Self::sample_counts(),
+                framebufferStencilSampleCounts: Self::sample_counts(),
+                framebufferNoAttachmentsSampleCounts: Self::sample_counts(),
+                maxColorAttachments: MAX_COLOR_ATTACHMENTS,
                 sampledImageColorSampleCounts: 0,
                 sampledImageIntegerSampleCounts: 0,
                 sampledImageDepthSampleCounts: 0,
                 sampledImageStencilSampleCounts: 0,
                 storageImageSampleCounts: 0,
-                maxSampleMaskWords: 0,
+                maxSampleMaskWords: 1,
                 timestampComputeAndGraphics: 0,
                 timestampPeriod: 0.0,
                 maxClipDistances: 0,
@@ -201,11 +214,11 @@ impl PhysicalDevice {
                 maxCombinedClipAndCullDistances: 0,
                 discreteQueuePriorities: 0,
                 pointSizeRange: [0.0, 0.0],
-                lineWidthRange: [0.0, 0.0],
+                lineWidthRange: [LINE_WIDTH_RANGE.0, LINE_WIDTH_RANGE.1],
                 pointSizeGranularity: 0.0,
-                lineWidthGranularity: 0.0,
+                lineWidthGranularity: LINE_WIDTH_GRANULARITY,
                 strictLines: 0,
-                standardSampleLocations: 0,
+                standardSampleLocations: VK_TRUE,
                 optimalBufferCopyOffsetAlignment: 0,
                 optimalBufferCopyRowPitchAlignment: 0,
                 nonCoherentAtomSize: 1,
@@ -285,17 +298,17 @@ impl PhysicalDevice {
             tessellationShader: VK_FALSE,
             sampleRateShading: VK_FALSE,
             dualSrcBlend: VK_FALSE,
-            logicOp: VK_FALSE,
-            multiDrawIndirect: VK_FALSE,
-            drawIndirectFirstInstance: VK_FALSE,
-            depthClamp: VK_FALSE,
-            depthBiasClamp: VK_FALSE,
+            logicOp: VK_TRUE,
+            multiDrawIndirect: VK_TRUE,
+            drawIndirectFirstInstance: VK_TRUE,
+            depthClamp: VK_TRUE,
+            depthBiasClamp: VK_TRUE,
             fillModeNonSolid: VK_FALSE,
-            depthBounds: VK_FALSE,
-            wideLines: VK_FALSE,
+            depthBounds: VK_TRUE,
+            wideLines: VK_TRUE,
             largePoints: VK_FALSE,
             alphaToOne: VK_FALSE,
-            multiViewport: VK_FALSE,
+            multiViewport: VK_TRUE,
             samplerAnisotropy: VK_FALSE,
             textureCompressionETC2: VK_TRUE,
             textureCompressionASTC_LDR: VK_TRUE,
@@ -1474,7 +1487,13 @@ impl PhysicalDevice {
                 ),
                 bufferFeatures: 0,
             },
-            VkFormat::VK_FORMAT_S8_UINT => unsupported,
+            VkFormat::VK_FORMAT_S8_UINT => VkFormatProperties {
+                linearTilingFeatures: 0,
+                optimalTilingFeatures: VkFormatFeatureFlags::from(
+                    VkFormatFeatureFlagBits::VK_FORMAT_FEATURE_DEPTH_STENCIL_ATTACHMENT_BIT,
+                ),
+                bufferFeatures: 0,
+            },
             VkFormat::VK_FORMAT_D16_UNORM_S8_UINT => unsupported,
             VkFormat::VK_FORMAT_D24_UNORM_S8_UINT => VkFormatProperties {
                 linearTilingFeatures: 0,
@@ -2157,7 +2176,7 @@ impl PhysicalDevice {
         &self,
         format: VkFormat,
         type_: VkImageType,
-        _tiling: VkImageTiling,
+        tiling: VkImageTiling,
         usage: VkImageUsageFlags,
         _flags: VkImageCreateFlags,
     ) -> Option<VkImageFormatProperties> {
@@ -2233,14 +2252,44 @@ impl PhysicalDevice {
                 maxExtent: max_extent,
                 maxMipLevels: 1,
                 maxArrayLayers: 1, // TODO: VkPhysicalDeviceLimits::maxImageArrayLayers
-                sampleCounts: VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT.into(),
+                sampleCounts: if tiling == VkImageTiling::VK_IMAGE_TILING_OPTIMAL
+                    && type_ == VkImageType::VK_IMAGE_TYPE_2D
+                {
+                    Self::sample_counts()
+                } else {
+                    VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT.into()
+                },
                 maxResourceSize: 2_u64.pow(31), // TODO: VK_ERROR_OUT_OF_DEVICE_MEMORY
             }),
             VkFormat::VK_FORMAT_R8G8B8A8_SNORM => None,
             VkFormat::VK_FORMAT_R8G8B8A8_USCALED => None,
             VkFormat::VK_FORMAT_R8G8B8A8_SSCALED => None,
-            VkFormat::VK_FORMAT_R8G8B8A8_UINT => None,
-            VkFormat::VK_FORMAT_R8G8B8A8_SINT => None,
+            VkFormat::VK_FORMAT_R8G8B8A8_UINT => Some(VkImageFormatProperties {
+                maxExtent: max_extent,
+                maxMipLevels: 1,
+                maxArrayLayers: 1, // TODO: VkPhysicalDeviceLimits::maxImageArrayLayers
+                sampleCounts: if tiling == VkImageTiling::VK_IMAGE_TILING_OPTIMAL
+                    && type_ == VkImageType::VK_IMAGE_TYPE_2D
+                {
+                    Self::sample_counts()
+                } else {
+                    VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT.into()
+                },
+                maxResourceSize: 2_u64.pow(31), // TODO: VK_ERROR_OUT_OF_DEVICE_MEMORY
+            }),
+            VkFormat::VK_FORMAT_R8G8B8A8_SINT => Some(VkImageFormatProperties {
+                maxExtent: max_extent,
+                maxMipLevels: 1,
+                maxArrayLayers: 1, // TODO: VkPhysicalDeviceLimits::maxImageArrayLayers
+                sampleCounts: if tiling == VkImageTiling::VK_IMAGE_TILING_OPTIMAL
+                    && type_ == VkImageType::VK_IMAGE_TYPE_2D
+                {
+                    Self::sample_counts()
+                } else {
+                    VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT.into()
+                },
+                maxResourceSize: 2_u64.pow(31), // TODO: VK_ERROR_OUT_OF_DEVICE_MEMORY
+            }),
             VkFormat::VK_FORMAT_R8G8B8A8_SRGB => None,
             VkFormat::VK_FORMAT_B8G8R8A8_UNORM => None,
             VkFormat::VK_FORMAT_B8G8R8A8_SNORM => None,
@@ -2588,6 +2637,14 @@ impl PhysicalDevice {
         }
     }
 
+    pub const fn parse_tessellation_state(
+        tessellation_state: VkPipelineTessellationStateCreateInfo,
+    ) -> TessellationState {
+        TessellationState {
+            patch_control_points: tessellation_state.patchControlPoints,
+        }
+    }
+
     pub(crate) fn parse_primitive_topology(topology: VkPrimitiveTopology) -> PrimitiveTopology {
         match topology {
             VkPrimitiveTopology::VK_PRIMITIVE_TOPOLOGY_POINT_LIST => PrimitiveTopology::PointList,
@@ -2634,41 +2691,49 @@ impl PhysicalDevice {
             let Some(viewport) = viewport_state.viewports.get_mut(i) else {
                 unreachable!()
             };
-            *viewport = Some(Viewport {
-                offset: Offset2 {
-                    x: vk_viewport.x,
-                    y: vk_viewport.y,
-                },
-                extent: Extent2 {
-                    width: vk_viewport.width,
-                    height: vk_viewport.height,
-                },
-                depth: Range2 {
-                    min: vk_viewport.minDepth,
-                    max: vk_viewport.maxDepth,
-                },
-            });
+            *viewport = Some(Self::parse_viewport(vk_viewport));
         }
         for (i, vk_scissor) in vk_scissors.iter().enumerate() {
             let Some(scissor) = viewport_state.scissors.get_mut(i) else {
                 unreachable!()
             };
-            *scissor = Some(Scissor {
-                render_area: RenderArea {
-                    extent: Extent2 {
-                        width: vk_scissor.extent.width,
-                        height: vk_scissor.extent.height,
-                    },
-                    offset: Offset2 {
-                        x: vk_scissor.offset.x,
-                        y: vk_scissor.offset.y,
-                    },
-                },
-            });
+            *scissor = Some(Self::parse_scissor(vk_scissor));
         }
         viewport_state
     }
 
+    pub const fn parse_viewport(vk_viewport: &VkViewport) -> Viewport {
+        Viewport {
+            offset: Offset2 {
+                x: vk_viewport.x,
+                y: vk_viewport.y,
+            },
+            extent: Extent2 {
+                width: vk_viewport.width,
+                height: vk_viewport.height,
+            },
+            depth: Range2 {
+                min: vk_viewport.minDepth,
+                max: vk_viewport.maxDepth,
+            },
+        }
+    }
+
+    pub const fn parse_scissor(vk_scissor: &VkRect2D) -> Scissor {
+        Scissor {
+            render_area: RenderArea {
+                extent: Extent2 {
+                    width: vk_scissor.extent.width,
+                    height: vk_scissor.extent.height,
+                },
+                offset: Offset2 {
+                    x: vk_scissor.offset.x,
+                    y: vk_scissor.offset.y,
+                },
+            },
+        }
+    }
+
     pub fn parse_rasterization_state(
         rasterization_state: VkPipelineRasterizationStateCreateInfo,
     ) -> RasterizationState {
@@ -2686,6 +2751,129 @@ impl PhysicalDevice {
         }
     }
 
+    pub fn parse_multisample_state(
+        multisample_state: VkPipelineMultisampleStateCreateInfo,
+    ) -> MultisampleState {
+        if multisample_state.sampleShadingEnable != 0 {
+            warn!("TODO: Sample shading");
+        }
+        if multisample_state.alphaToCoverageEnable != 0 || multisample_state.alphaToOneEnable != 0 {
+            warn!("TODO: Alpha to coverage and alpha to one");
+        }
+        MultisampleState {
+            rasterization_samples: multisample_state.rasterizationSamples.into(),
+            sample_mask: multisample_state
+                .pSampleMask
+                .map_or(!0, |x| unsafe { *x.as_ptr() }),
+        }
+    }
+
+    pub fn parse_depth_stencil_state(
+        depth_stencil_state: VkPipelineDepthStencilStateCreateInfo,
+    ) -> DepthStencilState {
+        DepthStencilState {
+            depth_test_enable: depth_stencil_state.depthTestEnable != 0,
+            depth_write_enable: depth_stencil_state.depthWriteEnable != 0,
+            depth_compare_op: depth_stencil_state.depthCompareOp.into(),
+            stencil_test_enable: depth_stencil_state.stencilTestEnable != 0,
+            front: Self::parse_stencil_op_state(depth_stencil_state.front),
+            back: Self::parse_stencil_op_state(depth_stencil_state.back),
+            depth_bounds_test_enable: depth_stencil_state.depthBoundsTestEnable != 0,
+            depth_bounds: Range2 {
+                min: depth_stencil_state.minDepthBounds,
+                max: depth_stencil_state.maxDepthBounds,
+            },
+        }
+    }
+
+    pub fn parse_color_blend_state(
+        color_blend_state: VkPipelineColorBlendStateCreateInfo,
+    ) -> ColorBlendState {
+        let attachments = color_blend_state
+            .pAttachments
+            .map_or(&[] as &[_], |x| unsafe {
+                std::slice::from_raw_parts(x.as_ptr(), color_blend_state.attachmentCount as usize)
+            });
+        let mut result = ColorBlendState {
+            blend_constants: color_blend_state.blendConstants,
+            logic_op_enable: color_blend_state.logicOpEnable != 0,
+            logic_op: color_blend_state.logicOp.into(),
+            ..Default::default()
+        };
+        for (attachment, vk_attachment) in result.attachments.iter_mut().zip(attachments) {
+            let color_write_mask = [
+                VkColorComponentFlagBits::VK_COLOR_COMPONENT_R_BIT,
+                VkColorComponentFlagBits::VK_COLOR_COMPONENT_G_BIT,
+                VkColorComponentFlagBits::VK_COLOR_COMPONENT_B_BIT,
+                VkColorComponentFlagBits::VK_COLOR_COMPONENT_A_BIT,
+            ]
+            .map(|bit| vk_attachment.colorWriteMask & bit.0 != 0);
+            *attachment = Some(ColorBlendAttachmentState {
+                blend_enable: vk_attachment.blendEnable != 0,
+                src_color_blend_factor: vk_attachment.srcColorBlendFactor.into(),
+                dst_color_blend_factor: vk_attachment.dstColorBlendFactor.into(),
+                color_blend_op: vk_attachment.colorBlendOp.into(),
+                src_alpha_blend_factor: vk_attachment.srcAlphaBlendFactor.into(),
+                dst_alpha_blend_factor: vk_attachment.dstAlphaBlendFactor.into(),
+                alpha_blend_op: vk_attachment.alphaBlendOp.into(),
+                color_write_mask,
+            });
+        }
+        result
+    }
+
+    pub fn parse_dynamic_state(dynamic_state: VkPipelineDynamicStateCreateInfo) -> DynamicStates {
+        let vk_dynamic_states = dynamic_state
+            .pDynamicStates
+            .map_or(&[] as &[_], |x| unsafe {
+                std::slice::from_raw_parts(x.as_ptr(), dynamic_state.dynamicStateCount as usize)
+            });
+
+        let mut dynamic_states = DynamicStates::default();
+        for &vk_dynamic_state in vk_dynamic_states {
+            match vk_dynamic_state {
+                VkDynamicState::VK_DYNAMIC_STATE_VIEWPORT => dynamic_states.viewport = true,
+                VkDynamicState::VK_DYNAMIC_STATE_SCISSOR => dynamic_states.scissor = true,
+                VkDynamicState::VK_DYNAMIC_STATE_LINE_WIDTH => dynamic_states.line_width = true,
+                VkDynamicState::VK_DYNAMIC_STATE_DEPTH_BIAS => dynamic_states.depth_bias = true,
+                VkDynamicState::VK_DYNAMIC_STATE_BLEND_CONSTANTS => {
+                    dynamic_states.blend_constants = true
+                }
+                VkDynamicState::VK_DYNAMIC_STATE_DEPTH_BOUNDS => dynamic_states.depth_bounds = true,
+                VkDynamicState::VK_DYNAMIC_STATE_STENCIL_COMPARE_MASK => {
+                    dynamic_states.stencil_compare_mask = true
+                }
+                VkDynamicState::VK_DYNAMIC_STATE_STENCIL_WRITE_MASK => {
+                    dynamic_states.stencil_write_mask = true
+                }
+                VkDynamicState::VK_DYNAMIC_STATE_STENCIL_REFERENCE => {
+                    dynamic_states.stencil_reference = true
+                }
+                _ => warn!("TODO: Dynamic state {:?}", vk_dynamic_state),
+            }
+        }
+        dynamic_states
+    }
+
+    pub const fn parse_stencil_faces(face_mask: VkStencilFaceFlags) -> StencilFaces {
+        StencilFaces {
+            front: face_mask & VkStencilFaceFlagBits::VK_STENCIL_FACE_FRONT_BIT.0 != 0,
+            back: face_mask & VkStencilFaceFlagBits::VK_STENCIL_FACE_BACK_BIT.0 != 0,
+        }
+    }
+
+    fn parse_stencil_op_state(stencil_op_state: VkStencilOpState) -> StencilOpState {
+        StencilOpState {
+            fail_op: stencil_op_state.failOp.into(),
+            pass_op: stencil_op_state.passOp.into(),
+            depth_fail_op: stencil_op_state.depthFailOp.into(),
+            compare_op: stencil_op_state.compareOp.into(),
+            compare_mask: stencil_op_state.compareMask,
+            write_mask: stencil_op_state.writeMask,
+            reference: stencil_op_state.reference,
+        }
+    }
+
     pub fn parse_shader_stages(
         shader_stages: &[VkPipelineShaderStageCreateInfo],
     ) -> Result<ShaderState, VkResult> {
@@ -5,7 +5,10 @@ use crate::context::NonDispatchable;
 use crate::image::ImageView;
 use crate::logical_device::LogicalDevice;
 use common::graphics::VertexInputState;
-use gpu::{Command, InputAssemblyState, RasterizationState, ViewportState};
+use gpu::{
+    ColorBlendState, Command, DepthStencilState, DynamicStates, InputAssemblyState,
+    MultisampleState, RasterizationState, TessellationState, ViewportState,
+};
 use headers::vk_decls::*;
 use log::*;
 use parking_lot::Mutex;
@@ -49,8 +52,7 @@ pub struct RenderPass {
     logical_device: Arc<Mutex<LogicalDevice>>,
     pub(crate) attachments: Arc<[AttachmentDescription]>,
     // TODO: dependencies: Arc<[VkSubpassDependency]>,
-    #[allow(dead_code)]
-    subpasses: Arc<[SubpassDescription]>,
+    pub(crate) subpasses: Arc<[SubpassDescription]>,
 }
 
 impl RenderPass {
@@ -167,8 +169,27 @@ pub struct Pipeline {
     pub shader_state: ShaderState,
     pub vertex_input_state: VertexInputState,
     pub input_assembly_state: InputAssemblyState,
+    pub tessellation_state: TessellationState,
     pub viewport_state: ViewportState,
     pub rasterization_state: RasterizationState,
+    pub multisample_state: MultisampleState,
+    pub depth_stencil_state: DepthStencilState,
+    pub color_blend_state: ColorBlendState,
+    pub dynamic_states: DynamicStates,
+}
+
+/// Fixed-function states parsed from `VkGraphicsPipelineCreateInfo`, `None` if not provided.
+#[derive(Debug, Default)]
+pub struct GraphicsPipelineStates {
+    pub vertex_input_state: Option<VertexInputState>,
+    pub input_assembly_state: Option<InputAssemblyState>,
+    pub tessellation_state: Option<TessellationState>,
+    pub viewport_state: Option<ViewportState>,
+    pub rasterization_state: Option<RasterizationState>,
+    pub multisample_state: Option<MultisampleState>,
+    pub depth_stencil_state: Option<DepthStencilState>,
+    pub color_blend_state: Option<ColorBlendState>,
+    pub dynamic_states: Option<DynamicStates>,
 }
 
 impl Pipeline {
@@ -176,10 +197,7 @@ impl Pipeline {
         logical_device: Arc<Mutex<LogicalDevice>>,
         pipeline_cache: Option<Arc<Mutex<PipelineCache>>>,
         shader_state: ShaderState,
-        vertex_input_state: Option<VertexInputState>,
-        input_assembly_state: Option<InputAssemblyState>,
-        viewport_state: Option<ViewportState>,
-        rasterization_state: Option<RasterizationState>,
+        states: GraphicsPipelineStates,
     ) -> VkNonDispatchableHandle {
         info!("new Pipeline");
         let handle = VK_NULL_HANDLE;
@@ -189,10 +207,15 @@ impl Pipeline {
             logical_device,
             pipeline_cache,
             shader_state,
-            vertex_input_state: vertex_input_state.unwrap_or_default(),
-            input_assembly_state: input_assembly_state.unwrap_or_default(),
-            viewport_state: viewport_state.unwrap_or_default(),
-            rasterization_state: rasterization_state.unwrap_or_default(),
+            vertex_input_state: states.vertex_input_state.unwrap_or_default(),
+            input_assembly_state: states.input_assembly_state.unwrap_or_default(),
+            tessellation_state: states.tessellation_state.unwrap_or_default(),
+            viewport_state: states.viewport_state.unwrap_or_default(),
+            rasterization_state: states.rasterization_state.unwrap_or_default(),
+            multisample_state: states.multisample_state.unwrap_or_default(),
+            depth_stencil_state: states.depth_stencil_state.unwrap_or_default(),
+            color_blend_state: states.color_blend_state.unwrap_or_default(),
+            dynamic_states: states.dynamic_states.unwrap_or_default(),
         };
         object.register_object()
     }
@@ -207,12 +230,27 @@ impl Pipeline {
         command_buffer.record(Command::SetInputAssemblyState {
             input_assembly_state: self.input_assembly_state.clone(),
         });
+        command_buffer.record(Command::SetTessellationState {
+            tessellation_state: self.tessellation_state.clone(),
+        });
         command_buffer.record(Command::SetViewportState {
             viewport_state: self.viewport_state.clone(),
         });
         command_buffer.record(Command::SetRasterizationState {
             rasterization_state: self.rasterization_state.clone(),
         });
+        command_buffer.record(Command::SetMultisampleState {
+            multisample_state: self.multisample_state.clone(),
+        });
+        command_buffer.record(Command::SetDepthStencilState {
+            depth_stencil_state: self.depth_stencil_state.clone(),
+        });
+        command_buffer.record(Command::SetColorBlendState {
+            color_blend_state: self.color_blend_state.clone(),
+        });
+        command_buffer.record(Command::SetDynamicStates {
+            dynamic_states: self.dynamic_states,
+        });
         warn!("TODO: Record rest of pipeline state");
     }
 }
@@ -224,8 +262,8 @@ pub struct Framebuffer {
     pub(crate) handle: VkNonDispatchableHandle,
     logical_device: Arc<Mutex<LogicalDevice>>,
     flags: VkFramebufferCreateFlagBits,
-    width: u32,
-    height: u32,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
     layers: u32,
     pub(crate) attachments: Arc<[Arc<Mutex<ImageView>>]>,
     render_pass: Arc<Mutex<RenderPass>>,
@@ -56,6 +56,7 @@ impl Swapchain {
                 extent.width,
                 extent.height,
                 extent.depth,
+                VkSampleCountFlagBits::VK_SAMPLE_COUNT_1_BIT,
                 create_info.imageUsage,
             );
             let Some(image) = Image::from_handle(image) else {
@@ -1,7 +1,10 @@
+pub use crate::il::Interpolation;
 use crate::interpreter::Interpreter;
-use common::consts::MAX_CLIP_DISTANCES;
+use common::consts::{
+    MAX_CLIP_DISTANCES, MAX_COLOR_ATTACHMENTS, MAX_SAMPLE_COUNT, MAX_VARYING_LOCATIONS,
+};
 use common::graphics::VertexInputState;
-use common::math::{Color, Fragment, Position, Vertex};
+use common::math::{Color, Fragment, FragmentQuad, Position, Vector4, Vertex};
 
 #[derive(Debug, Clone, Default)]
 pub struct ShaderState {
@@ -43,6 +46,10 @@ pub struct VertexShaderOutput {
     pub vertex_index: u32,
     // gl_ClipDistances
     pub clip_distances: [f32; MAX_CLIP_DISTANCES as usize],
+    // gl_ViewportIndex
+    pub viewport_index: u32,
+    // User outputs indexed by `Location`
+    pub varyings: [Vector4; MAX_VARYING_LOCATIONS as usize],
     // TODO: Determine shader output interface using OpEntryPoints and use it to initialize ShaderOutput
     //       https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#interfaces
 }
@@ -54,6 +61,58 @@ impl Default for VertexShaderOutput {
             point_size: 1.0,
             vertex_index: 0,
             clip_distances: [0.0f32, 0.0f32, 0.0f32, 0.0f32],
+            viewport_index: 0,
+            varyings: Default::default(),
+        }
+    }
+}
+
+impl VertexShaderOutput {
+    /// Linearly interpolates outputs between `self` and `other` in clip space, used for vertices
+    /// created by clipping. Varyings with `NoPerspective` `interpolations` are interpolated
+    /// linearly in screen space instead.
+    pub fn lerp(
+        &self,
+        other: &Self,
+        t: f32,
+        interpolations: &[Interpolation; MAX_VARYING_LOCATIONS as usize],
+    ) -> Self {
+        let [x0, y0, z0, w0] = self.position.get_as_f32_array();
+        let [x1, y1, z1, w1] = other.position.get_as_f32_array();
+        // Screen-space parameter of the same point, varying times w is linear in clip space.
+        let w = (w1 - w0).mul_add(t, w0);
+        let t_screen = if w == 0.0 { t } else { t * w1 / w };
+        let lerp_with = |a: f32, b: f32, t: f32| (b - a).mul_add(t, a);
+        let lerp = |a: f32, b: f32| lerp_with(a, b, t);
+        let mut clip_distances = self.clip_distances;
+        for (i, clip_distance) in clip_distances.iter_mut().enumerate() {
+            *clip_distance = lerp(*clip_distance, other.clip_distances[i]);
+        }
+        let mut varyings = self.varyings;
+        for (i, varying) in varyings.iter_mut().enumerate() {
+            let t = if interpolations[i] == Interpolation::NoPerspective {
+                t_screen
+            } else {
+                t
+            };
+            let lerp = |a: f32, b: f32| lerp_with(a, b, t);
+            let [r0, g0, b0, a0] = varying.get_as_f32_array();
+            let [r1, g1, b1, a1] = other.varyings[i].get_as_f32_array();
+            *varying =
+                Vector4::from_sfloat32_raw(lerp(r0, r1), lerp(g0, g1), lerp(b0, b1), lerp(a0, a1));
+        }
+        Self {
+            position: Position::from_sfloat32_raw(
+                lerp(x0, x1),
+                lerp(y0, y1),
+                lerp(z0, z1),
+                lerp(w0, w1),
+            ),
+            point_size: lerp(self.point_size, other.point_size),
+            vertex_index: self.vertex_index,
+            clip_distances,
+            viewport_index: self.viewport_index,
+            varyings,
         }
     }
 }
@@ -65,27 +124,61 @@ impl From<Vertex> for VertexShaderOutput {
             point_size: vertex.point_size,
             vertex_index: vertex.index,
             clip_distances: vertex.clip_distances,
+            viewport_index: 0,
+            varyings: Default::default(),
         }
     }
 }
 
 impl Shader {
-    pub fn execute_fragment_shader(&self, fragments: Vec<Fragment>) -> Vec<FragmentShaderOutput> {
-        self.interpreter.execute_fragment_shader(fragments)
+    /// Returns interpolation of fragment shader inputs, indexed by `Location`.
+    pub fn input_interpolations(&self) -> [Interpolation; MAX_VARYING_LOCATIONS as usize] {
+        self.interpreter.input_interpolations()
+    }
+
+    /// Returns true for each input decorated with `Centroid`, indexed by `Location`.
+    pub fn input_centroids(&self) -> [bool; MAX_VARYING_LOCATIONS as usize] {
+        self.interpreter.input_centroids()
+    }
+
+    /// Returns true for each `Location` used by shader inputs.
+    pub fn input_locations(&self) -> [bool; MAX_VARYING_LOCATIONS as usize] {
+        self.interpreter.input_locations()
+    }
+
+    /// Returns true if per-fragment tests are performed before fragment shader execution,
+    /// either requested by `EarlyFragmentTests` execution mode or because shader can not
+    /// affect their results by writing `FragDepth` or discarding fragments.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#fragops-early-mode
+    pub fn early_fragment_tests(&self) -> bool {
+        self.interpreter.early_fragment_tests()
+            || (!self.interpreter.writes_frag_depth() && !self.interpreter.can_discard())
+    }
+
+    /// Executes fragment shader for quads of fragments, returns outputs of non-helper invocations.
+    pub fn execute_fragment_shader(&self, quads: Vec<FragmentQuad>) -> Vec<FragmentShaderOutput> {
+        self.interpreter.execute_fragment_shader(quads)
     }
 }
 
 #[derive(Debug, Copy, Clone, Default)]
 pub struct FragmentShaderOutput {
     pub position: Position,
-    pub color: Color,
+    /// Colors indexed by `Location`, `None` if shader does not write output at that location.
+    pub colors: [Option<Color>; MAX_COLOR_ATTACHMENTS as usize],
+    pub front_facing: bool,
+    pub sample_mask: u32,
+    pub sample_depths: [f32; MAX_SAMPLE_COUNT as usize],
 }
 
 impl From<Fragment> for FragmentShaderOutput {
     fn from(fragment: Fragment) -> Self {
         Self {
             position: fragment.position,
-            color: fragment.color,
+            colors: [None; MAX_COLOR_ATTACHMENTS as usize],
+            front_facing: fragment.front_facing,
+            sample_mask: fragment.sample_mask,
+            sample_depths: fragment.sample_depths,
         }
     }
 }
@@ -100,6 +193,12 @@ mod tests {
 
     use super::*;
 
+    use rspirv::binary::Assemble;
+    use rspirv::dr::{Builder, Operand};
+    use rspirv::spirv::{
+        AddressingModel, Capability, Decoration, ExecutionMode, ExecutionModel, FunctionControl,
+        MemoryModel, StorageClass,
+    };
     use std::process::Command;
 
     fn compile_glsl(stage: &str, glsl_code: &str) -> Vec<u32> {
@@ -131,6 +230,13 @@ mod tests {
             .collect::<Vec<_>>()
     }
 
+    /// Vertex input attributes with only `Location` 0 set.
+    fn location_0_attributes(attribute: Vector4) -> [Vector4; MAX_VERTEX_ATTRIBUTES as usize] {
+        let mut attributes = [Vector4::default(); MAX_VERTEX_ATTRIBUTES as usize];
+        attributes[0] = attribute;
+        attributes
+    }
+
     #[test]
     fn vertex_shader_set_output() {
         let spv = compile_glsl(
@@ -164,7 +270,9 @@ mod tests {
             position: Position::from_raw(10, 20, 30, 40).to_unorm8(),
             point_size: 1.0f32,
             index: 1,
+            instance_index: 0,
             clip_distances: [0.8f32, 0.4f32, 0.2f32, 0.1f32],
+            attributes: Default::default(),
         }];
         let expected = inputs
             .iter()
@@ -211,7 +319,9 @@ mod tests {
             position: Position::from_raw(10, 20, 30, 40).to_unorm8(),
             point_size: 1.0f32,
             index: 1,
+            instance_index: 0,
             clip_distances: [0.8f32, 0.4f32, 0.2f32, 0.1f32],
+            attributes: Default::default(),
         }];
         let expected = inputs
             .iter()
@@ -256,7 +366,9 @@ mod tests {
             position: Position::from_raw(10, 20, 30, 40).to_unorm8(),
             point_size: 1.0f32,
             index: 1,
+            instance_index: 0,
             clip_distances: [0.0f32, 0.0f32, 0.0f32, 0.0f32],
+            attributes: Default::default(),
         }];
         let expected = inputs.iter().map(|&x| x.into()).collect::<Vec<_>>();
         let outputs = shader
@@ -306,22 +418,28 @@ mod tests {
         // R8G8 vec2
         let inputs = vec![
             Vertex {
-                position: Position::from_raw(101, 228, 0, 0).to_unorm8(),
+                position: Default::default(),
                 point_size: 1.0f32,
                 index: 0,
+                instance_index: 0,
                 clip_distances: Default::default(),
+                attributes: location_0_attributes(Position::from_raw(101, 228, 0, 0).to_unorm8()),
             },
             Vertex {
-                position: Position::from_raw(161, 201, 0, 0).to_unorm8(),
+                position: Default::default(),
                 point_size: 1.0f32,
                 index: 0,
+                instance_index: 0,
                 clip_distances: Default::default(),
+                attributes: location_0_attributes(Position::from_raw(161, 201, 0, 0).to_unorm8()),
             },
             Vertex {
-                position: Position::from_raw(243, 120, 0, 0).to_unorm8(),
+                position: Default::default(),
                 point_size: 1.0f32,
                 index: 0,
+                instance_index: 0,
                 clip_distances: Default::default(),
+                attributes: location_0_attributes(Position::from_raw(243, 120, 0, 0).to_unorm8()),
             },
         ];
 
@@ -358,4 +476,297 @@ mod tests {
             assert_eq!(output.point_size, point_size);
         }
     }
+
+    fn instance_index_vertex_shader() -> Vec<u32> {
+        compile_glsl(
+            "vert",
+            r#"
+            #version 450
+            layout(location = 0) in vec4 position;
+            layout(location = 0) out vec4 instance;
+            void main() {
+                gl_Position = position;
+                instance = vec4(float(gl_InstanceIndex), 0.0f, 0.0f, 1.0f);
+            }
+            "#,
+        )
+    }
+
+    #[test]
+    fn vertex_shader_instance_index() {
+        let shader =
+            Shader::new("main", instance_index_vertex_shader()).expect("shader should compile");
+        let vertex_input_state = VertexInputState {
+            attributes: [None; MAX_VERTEX_ATTRIBUTES as usize],
+            bindings: [None; MAX_VERTEX_BINDINGS as usize],
+        };
+        let inputs = [(0, 3), (1, 3), (0, 4)]
+            .map(|(index, instance_index)| Vertex {
+                position: Default::default(),
+                point_size: 1.0f32,
+                index,
+                instance_index,
+                clip_distances: Default::default(),
+                attributes: location_0_attributes(Position::from_sfloat32_raw(
+                    index as f32,
+                    instance_index as f32,
+                    0.0,
+                    1.0,
+                )),
+            })
+            .to_vec();
+        let expected = inputs
+            .iter()
+            .map(|&x| {
+                let mut varyings = [Vector4::default(); MAX_VARYING_LOCATIONS as usize];
+                varyings[0] = Vector4::from_sfloat32_raw(x.instance_index as f32, 0.0, 0.0, 1.0);
+                VertexShaderOutput {
+                    position: x.attributes[0],
+                    varyings,
+                    ..x.into()
+                }
+            })
+            .collect::<Vec<_>>();
+        let outputs = shader.execute_vertex_shader(&vertex_input_state, inputs);
+        assert_eq!(outputs, expected);
+    }
+
+    /// Assembles fragment shader loading whole input block, which GLSL can not express:
+    /// ```glsl
+    /// #version 450
+    /// in Block {
+    ///     layout(location = 0) vec4 color;
+    /// } block;
+    /// void main() {
+    ///     Block copy = block;
+    /// }
+    /// ```
+    fn load_input_block_fragment_shader() -> Vec<u32> {
+        let mut b = Builder::new();
+        b.set_version(1, 0);
+        b.capability(Capability::Shader);
+        b.memory_model(AddressingModel::Logical, MemoryModel::GLSL450);
+        let void = b.type_void();
+        let function = b.type_function(void, []);
+        let float = b.type_float(32);
+        let vec4 = b.type_vector(float, 4);
+        let block_type = b.type_struct([vec4]);
+        b.decorate(block_type, Decoration::Block, []);
+        b.member_decorate(
+            block_type,
+            0,
+            Decoration::Location,
+            [Operand::LiteralInt32(0)],
+        );
+        let pointer = b.type_pointer(None, StorageClass::Input, block_type);
+        let block = b.variable(pointer, None, StorageClass::Input, None);
+
+        let main = b
+            .begin_function(void, None, FunctionControl::NONE, function)
+            .expect("function should begin");
+        b.begin_block(None).expect("block should begin");
+        b.load(block_type, None, block, None, [])
+            .expect("instruction should be in block");
+        b.ret().expect("return should end block");
+        b.end_function().expect("function should end");
+        b.entry_point(ExecutionModel::Fragment, main, "main", [block]);
+        b.execution_mode(main, ExecutionMode::OriginUpperLeft, []);
+        b.module().assemble()
+    }
+
+    #[test]
+    fn load_of_input_block_compiles() {
+        let shader = Shader::new("main", load_input_block_fragment_shader());
+        assert!(shader.is_ok());
+    }
+
+    fn array_and_block_inputs_fragment_shader() -> Vec<u32> {
+        compile_glsl(
+            "frag",
+            r#"
+            #version 450
+            layout(location = 0) flat in ivec4 ids[2];
+            in Block {
+                layout(location = 2) flat vec4 a;
+                layout(location = 3) noperspective vec4 b;
+            } block;
+            layout(location = 4) in vec4 color;
+            void main() {}
+            "#,
+        )
+    }
+
+    #[test]
+    fn interpolation_of_array_elements_and_block_members() {
+        let shader = Shader::new("main", array_and_block_inputs_fragment_shader())
+            .expect("shader should compile");
+        let interpolations = shader.input_interpolations();
+        assert_eq!(
+            interpolations[..5],
+            [
+                Interpolation::Flat,
+                Interpolation::Flat,
+                Interpolation::Flat,
+                Interpolation::NoPerspective,
+                Interpolation::Perspective,
+            ]
+        );
+    }
+
+    /// Quad with top-left pixel (2, 4) and `Location` 0 input `(2x + 3y, xy, 0, 0)` of pixel
+    /// center (x, y), lanes in `helpers` are helper invocations.
+    fn fragment_quad(helpers: [bool; 4]) -> FragmentQuad {
+        std::array::from_fn(|lane| {
+            let x = 2.5 + (lane % 2) as f32;
+            let y = 4.5 + (lane / 2) as f32;
+            let mut varyings = [Vector4::default(); MAX_VARYING_LOCATIONS as usize];
+            varyings[0] = Vector4::from_sfloat32_raw(2.0f32.mul_add(x, 3.0 * y), x * y, 0.0, 0.0);
+            Fragment {
+                position: Position::from_sfloat32_raw(x, y, 0.5, 1.0),
+                varyings,
+                front_facing: true,
+                sample_mask: u32::from(!helpers[lane]),
+                sample_depths: Default::default(),
+            }
+        })
+    }
+
+    /// Returns first two components of outputs at `locations` of each fragment.
+    fn two_components(outputs: &[FragmentShaderOutput], locations: usize) -> Vec<Vec<[f32; 2]>> {
+        outputs
+            .iter()
+            .map(|output| {
+                output.colors[..locations]
+                    .iter()
+                    .map(|color| {
+                        let [r, g, ..] =
+                            color.expect("output should be written").get_as_f32_array();
+                        [r, g]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn derivatives_fragment_shader(precision: &str) -> Shader {
+        let spv = compile_glsl(
+            "frag",
+            &format!(
+                r#"
+                #version 450
+                layout(location = 0) in vec4 v;
+                layout(location = 0) out vec4 dx;
+                layout(location = 1) out vec4 dy;
+                layout(location = 2) out vec4 width;
+                void main() {{
+                    dx = dFdx{precision}(v);
+                    dy = dFdy{precision}(v);
+                    width = fwidth{precision}(v);
+                }}
+                "#
+            ),
+        );
+        Shader::new("main", spv).expect("shader should compile")
+    }
+
+    #[test]
+    fn fine_derivatives_are_differences_within_row_and_column_of_lane() {
+        // Derivatives without explicit precision are computed as fine ones.
+        for precision in ["Fine", ""] {
+            let shader = derivatives_fragment_shader(precision);
+            let outputs = two_components(
+                &shader.execute_fragment_shader(vec![fragment_quad([false; 4])]),
+                3,
+            );
+            // d(xy)/dx is y of lane's row, d(xy)/dy is x of lane's column.
+            assert_eq!(
+                outputs,
+                vec![
+                    vec![[2.0, 4.5], [3.0, 2.5], [5.0, 7.0]],
+                    vec![[2.0, 4.5], [3.0, 3.5], [5.0, 8.0]],
+                    vec![[2.0, 5.5], [3.0, 2.5], [5.0, 8.0]],
+                    vec![[2.0, 5.5], [3.0, 3.5], [5.0, 9.0]],
+                ],
+                "{precision}"
+            );
+        }
+    }
+
+    #[test]
+    fn coarse_derivatives_are_same_for_all_lanes() {
+        let shader = derivatives_fragment_shader("Coarse");
+        let outputs = two_components(
+            &shader.execute_fragment_shader(vec![fragment_quad([false; 4])]),
+            3,
+        );
+        assert_eq!(outputs, vec![vec![[2.0, 4.5], [3.0, 2.5], [5.0, 7.0]]; 4]);
+    }
+
+    #[test]
+    fn helper_invocations_provide_derivatives_without_outputs() {
+        let spv = compile_glsl(
+            "frag",
+            r#"
+            #version 450
+            layout(location = 0) in vec4 v;
+            layout(location = 0) out vec4 helper;
+            layout(location = 1) out vec4 dx;
+            void main() {
+                float h = float(gl_HelperInvocation);
+                helper = vec4(h, dFdxFine(h), dFdyFine(h), 0.0);
+                dx = dFdxFine(v);
+            }
+            "#,
+        );
+        let shader = Shader::new("main", spv).expect("shader should compile");
+        // Only helper lanes 1 and 2 see `gl_HelperInvocation`, they are neighbors of lanes 0 and 3
+        // in both directions. Inputs of helpers still contribute to derivatives.
+        let outputs =
+            shader.execute_fragment_shader(vec![fragment_quad([false, true, true, false])]);
+        assert_eq!(
+            outputs
+                .iter()
+                .map(|output| output.position.get_as_f32_array())
+                .collect::<Vec<_>>(),
+            vec![[2.5, 4.5, 0.5, 1.0], [3.5, 5.5, 0.5, 1.0]]
+        );
+        let helpers = outputs
+            .iter()
+            .map(|output| output.colors[0].map(|color| color.get_as_f32_array()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            helpers,
+            vec![Some([0.0, 1.0, 1.0, 0.0]), Some([0.0, -1.0, -1.0, 0.0])]
+        );
+        assert_eq!(
+            two_components(&outputs, 2)
+                .into_iter()
+                .map(|colors| colors[1])
+                .collect::<Vec<_>>(),
+            vec![[2.0, 4.5], [2.0, 5.5]]
+        );
+    }
+
+    #[test]
+    fn killed_invocations_have_no_outputs() {
+        let spv = compile_glsl(
+            "frag",
+            r#"
+            #version 450
+            layout(location = 0) in vec4 v;
+            layout(location = 0) out vec4 dx;
+            void main() {
+                dx = dFdxFine(v);
+                discard;
+            }
+            "#,
+        );
+        let shader = Shader::new("main", spv).expect("shader should compile");
+        assert!(shader.interpreter.can_discard());
+        let quads = vec![
+            fragment_quad([false; 4]),
+            fragment_quad([true, false, false, true]),
+        ];
+        assert_eq!(shader.execute_fragment_shader(quads).len(), 0);
+    }
 }
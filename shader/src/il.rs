@@ -1,17 +1,24 @@
 use crate::spirv;
 use crate::spirv::Spirv;
 use anyhow::Context;
+use common::consts::MAX_VARYING_LOCATIONS;
+use itertools::Itertools;
 
 #[derive(Debug, Clone)]
 pub struct Il {
     pub(crate) instructions: Vec<Instruction>,
+    pub(crate) execution_modes: spirv::ExecutionModes,
 }
 
 impl Il {
     pub fn new(name: &str, code: Vec<u32>) -> anyhow::Result<Self> {
         let spirv = Spirv::new(name, code)?;
+        let execution_modes = spirv.execution_modes.clone();
         let instructions = Self::parse_spirv(spirv)?;
-        Ok(Self { instructions })
+        Ok(Self {
+            instructions,
+            execution_modes,
+        })
     }
 }
 
@@ -144,6 +151,21 @@ pub enum Instruction {
         id: Variable,
         op: Variable,
     },
+    MathDPdx {
+        id: Variable,
+        p: Variable,
+        coarse: bool,
+    },
+    MathDPdy {
+        id: Variable,
+        p: Variable,
+        coarse: bool,
+    },
+    MathFwidth {
+        id: Variable,
+        p: Variable,
+        coarse: bool,
+    },
     Return,
     Select {
         id: Variable,
@@ -216,8 +238,12 @@ impl Il {
                     let decl = Self::get_variable_decl(
                         &spirv,
                         &memory_object.type_,
-                        Self::from_spirv_decorations(&memory_object.decorations),
-                    );
+                        Self::from_spirv_decorations(
+                            &memory_object.decorations,
+                            Some(memory_object.storage_class),
+                        ),
+                    )
+                    .with_interpolation_decorations(&memory_object.decorations);
                     let id = Variable::from_spirv(id);
                     pointer_variables.push(Instruction::VariableDecl { id, decl });
                 }
@@ -525,6 +551,54 @@ impl Il {
                         op: Variable::from_spirv(operand),
                     });
                 }
+                spirv::Instruction::DPdx {
+                    result_id,
+                    result_type,
+                    p,
+                    coarse,
+                } => {
+                    let decl =
+                        Self::get_variable_decl(&spirv, result_type, VariableBacking::Memory);
+                    let id = Variable::from_spirv(result_id);
+                    instructions.push(Instruction::VariableDecl { id, decl });
+                    instructions.push(Instruction::MathDPdx {
+                        id,
+                        p: Variable::from_spirv(p),
+                        coarse: *coarse,
+                    });
+                }
+                spirv::Instruction::DPdy {
+                    result_id,
+                    result_type,
+                    p,
+                    coarse,
+                } => {
+                    let decl =
+                        Self::get_variable_decl(&spirv, result_type, VariableBacking::Memory);
+                    let id = Variable::from_spirv(result_id);
+                    instructions.push(Instruction::VariableDecl { id, decl });
+                    instructions.push(Instruction::MathDPdy {
+                        id,
+                        p: Variable::from_spirv(p),
+                        coarse: *coarse,
+                    });
+                }
+                spirv::Instruction::Fwidth {
+                    result_id,
+                    result_type,
+                    p,
+                    coarse,
+                } => {
+                    let decl =
+                        Self::get_variable_decl(&spirv, result_type, VariableBacking::Memory);
+                    let id = Variable::from_spirv(result_id);
+                    instructions.push(Instruction::VariableDecl { id, decl });
+                    instructions.push(Instruction::MathFwidth {
+                        id,
+                        p: Variable::from_spirv(p),
+                        coarse: *coarse,
+                    });
+                }
                 spirv::Instruction::CompositeExtract {
                     result_id,
                     result_type,
@@ -651,19 +725,34 @@ impl Il {
         }
     }
 
-    const fn from_spirv_decorations(decorations: &spirv::Decorations) -> VariableBacking {
+    const fn from_spirv_decorations(
+        decorations: &spirv::Decorations,
+        storage_class: Option<spirv::StorageClass>,
+    ) -> VariableBacking {
         if let Some(builtin) = decorations.builtin {
             match builtin {
                 spirv::BuiltInDecoration::Position => VariableBacking::Position,
                 spirv::BuiltInDecoration::PointSize => VariableBacking::PointSize,
                 spirv::BuiltInDecoration::VertexIndex => VariableBacking::VertexIndex,
+                spirv::BuiltInDecoration::InstanceIndex => VariableBacking::InstanceIndex,
                 spirv::BuiltInDecoration::FragCoord => VariableBacking::FragCoord,
+                spirv::BuiltInDecoration::FragDepth => VariableBacking::FragDepth,
                 spirv::BuiltInDecoration::ClipDistance => VariableBacking::ClipDistance,
                 spirv::BuiltInDecoration::CullDistance => VariableBacking::CullDistance,
+                spirv::BuiltInDecoration::FrontFacing => VariableBacking::FrontFacing,
+                spirv::BuiltInDecoration::HelperInvocation => VariableBacking::HelperInvocation,
+                spirv::BuiltInDecoration::ViewportIndex => VariableBacking::ViewportIndex,
             }
         } else if let Some(location) = decorations.location {
-            VariableBacking::Location {
-                number: location.number,
+            match storage_class {
+                Some(spirv::StorageClass::Input) => VariableBacking::InputLocation {
+                    number: location.number,
+                },
+                Some(spirv::StorageClass::Output) => VariableBacking::OutputLocation {
+                    number: location.number,
+                },
+                // Members of loaded interface blocks are ordinary values.
+                _ => VariableBacking::Memory,
             }
         } else {
             VariableBacking::Memory
@@ -674,6 +763,17 @@ impl Il {
         spirv: &Spirv,
         type_id: &spirv::ObjectId,
         backing: VariableBacking,
+    ) -> VariableDecl {
+        Self::get_interface_variable_decl(spirv, type_id, backing, None)
+    }
+
+    /// Creates declaration of variable, `storage_class` of pointer is used to determine
+    /// whether `Location` decorated members are shader inputs or outputs.
+    fn get_interface_variable_decl(
+        spirv: &Spirv,
+        type_id: &spirv::ObjectId,
+        backing: VariableBacking,
+        storage_class: Option<spirv::StorageClass>,
     ) -> VariableDecl {
         let (kind, component_count, backing) = match Self::get_spirv_type(spirv, type_id) {
             spirv::Type::Float { width } => {
@@ -698,7 +798,8 @@ impl Il {
                 length,
                 decorations,
             } => {
-                let element_type = Self::get_variable_decl(spirv, element_type, backing);
+                let element_type =
+                    Self::get_interface_variable_decl(spirv, element_type, backing, storage_class);
                 let &spirv::Constant::Scalar {
                     type_: _,
                     value: length,
@@ -724,7 +825,12 @@ impl Il {
                 component_type,
                 component_count,
             } => {
-                let component_type = Self::get_variable_decl(spirv, component_type, backing);
+                let component_type = Self::get_interface_variable_decl(
+                    spirv,
+                    component_type,
+                    backing,
+                    storage_class,
+                );
                 if component_type.component_count == 1 {
                     (
                         component_type.kind,
@@ -742,11 +848,13 @@ impl Il {
                 let members = member_types
                     .iter()
                     .map(|spirv::MemberType { type_, decorations }| {
-                        Self::get_variable_decl(
+                        Self::get_interface_variable_decl(
                             spirv,
                             type_,
-                            Self::from_spirv_decorations(decorations),
+                            Self::from_spirv_decorations(decorations, storage_class),
+                            storage_class,
                         )
+                        .with_interpolation_decorations(decorations)
                     })
                     .collect();
                 assert!(decorations.block);
@@ -757,10 +865,11 @@ impl Il {
                 )
             }
             spirv::Type::Pointer {
-                storage_class: _,
+                storage_class,
                 type_,
             } => {
-                let type_ = Self::get_variable_decl(spirv, type_, backing);
+                let type_ =
+                    Self::get_interface_variable_decl(spirv, type_, backing, Some(*storage_class));
                 (
                     VariableKind::Pointer,
                     1,
@@ -775,7 +884,83 @@ impl Il {
             kind,
             component_count,
             backing,
+            interpolation: Interpolation::default(),
+            centroid: false,
+        }
+    }
+}
+
+impl Il {
+    /// Returns interpolation of shader inputs, indexed by `Location`.
+    pub fn input_interpolations(&self) -> [Interpolation; MAX_VARYING_LOCATIONS as usize] {
+        let mut interpolations = [Interpolation::default(); MAX_VARYING_LOCATIONS as usize];
+        for (number, interpolation, _) in self.input_location_decorations() {
+            interpolations[number as usize] = interpolation;
+        }
+        interpolations
+    }
+
+    /// Returns true for each shader input decorated with `Centroid`, indexed by `Location`.
+    pub fn input_centroids(&self) -> [bool; MAX_VARYING_LOCATIONS as usize] {
+        let mut centroids = [false; MAX_VARYING_LOCATIONS as usize];
+        for (number, _, centroid) in self.input_location_decorations() {
+            centroids[number as usize] = centroid;
+        }
+        centroids
+    }
+
+    /// Returns true for each `Location` used by shader inputs.
+    pub fn input_locations(&self) -> [bool; MAX_VARYING_LOCATIONS as usize] {
+        let mut locations = [false; MAX_VARYING_LOCATIONS as usize];
+        for (number, ..) in self.input_location_decorations() {
+            locations[number as usize] = true;
+        }
+        locations
+    }
+
+    /// Returns every `Location` occupied by shader inputs, including elements of arrays and
+    /// members of blocks, together with its interpolation decorations.
+    fn input_location_decorations(&self) -> Vec<(u32, Interpolation, bool)> {
+        let mut locations = vec![];
+        for instruction in &self.instructions {
+            if let Instruction::VariableDecl { decl, .. } = instruction {
+                decl.collect_input_locations(Interpolation::default(), false, &mut locations);
+            }
+        }
+        // Interface variables are declared before access chains into them, keep their
+        // decorations.
+        locations
+            .into_iter()
+            .unique_by(|&(number, ..)| number)
+            .collect()
+    }
+
+    /// Returns true for each `Location` used by shader outputs.
+    pub fn output_locations(&self) -> [bool; MAX_VARYING_LOCATIONS as usize] {
+        let mut locations = [false; MAX_VARYING_LOCATIONS as usize];
+        for instruction in &self.instructions {
+            let Instruction::VariableDecl { decl, .. } = instruction else {
+                continue;
+            };
+            if let Some(number) = decl.output_location() {
+                locations[number as usize] = true;
+            }
         }
+        locations
+    }
+
+    /// Returns true if shader statically uses `FragDepth` output.
+    pub fn writes_frag_depth(&self) -> bool {
+        self.instructions.iter().any(|instruction| {
+            matches!(instruction, Instruction::VariableDecl { decl, .. } if decl.is_frag_depth())
+        })
+    }
+
+    /// Returns true if shader contains `OpKill`.
+    pub fn can_discard(&self) -> bool {
+        self.instructions
+            .iter()
+            .any(|instruction| matches!(instruction, Instruction::Kill))
     }
 }
 
@@ -790,6 +975,93 @@ pub struct VariableDecl {
     pub(crate) kind: VariableKind,
     pub(crate) component_count: u32,
     pub(crate) backing: VariableBacking,
+    pub(crate) interpolation: Interpolation,
+    /// Input is interpolated within covered area of partially covered pixel.
+    pub(crate) centroid: bool,
+}
+
+impl VariableDecl {
+    const fn with_interpolation_decorations(mut self, decorations: &spirv::Decorations) -> Self {
+        self.interpolation = if decorations.flat {
+            Interpolation::Flat
+        } else if decorations.no_perspective {
+            Interpolation::NoPerspective
+        } else {
+            Interpolation::Perspective
+        };
+        self.centroid = decorations.centroid;
+        self
+    }
+
+    /// Collects `Location`s occupied by input, members and elements without own interpolation
+    /// decorations inherit those of enclosing block or array.
+    fn collect_input_locations(
+        &self,
+        interpolation: Interpolation,
+        centroid: bool,
+        locations: &mut Vec<(u32, Interpolation, bool)>,
+    ) {
+        let interpolation = if self.interpolation == Interpolation::default() {
+            interpolation
+        } else {
+            self.interpolation
+        };
+        let centroid = centroid || self.centroid;
+        match &self.backing {
+            VariableBacking::InputLocation { number } => {
+                locations.push((*number, interpolation, centroid));
+            }
+            VariableBacking::Array { element_kind, .. } => {
+                // Elements occupy consecutive locations starting at the decorated one.
+                let first = locations.len();
+                element_kind.collect_input_locations(interpolation, centroid, locations);
+                let element_locations = locations[first..].to_vec();
+                let stride = element_locations.len() as u32;
+                for i in 1..self.component_count {
+                    locations.extend(element_locations.iter().map(
+                        |&(number, interpolation, centroid)| {
+                            (number + i * stride, interpolation, centroid)
+                        },
+                    ));
+                }
+            }
+            VariableBacking::Struct { members } => {
+                for member in members {
+                    member.collect_input_locations(interpolation, centroid, locations);
+                }
+            }
+            VariableBacking::Pointer { kind } => {
+                kind.collect_input_locations(interpolation, centroid, locations);
+            }
+            _ => {}
+        }
+    }
+
+    fn output_location(&self) -> Option<u32> {
+        match &self.backing {
+            VariableBacking::OutputLocation { number } => Some(*number),
+            VariableBacking::Pointer { kind } => kind.output_location(),
+            _ => None,
+        }
+    }
+
+    fn is_frag_depth(&self) -> bool {
+        match &self.backing {
+            VariableBacking::FragDepth => true,
+            VariableBacking::Pointer { kind } => kind.is_frag_depth(),
+            _ => false,
+        }
+    }
+}
+
+/// Interpolation of fragment shader inputs.
+// NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#shaders-interpolation-decorations
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub enum Interpolation {
+    #[default]
+    Perspective,
+    NoPerspective,
+    Flat,
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -807,15 +1079,23 @@ pub enum VariableKind {
 #[derive(Debug, Clone)]
 pub enum VariableBacking {
     Memory,
-    Location {
+    InputLocation {
+        number: u32,
+    },
+    OutputLocation {
         number: u32,
     },
     Position,
     PointSize,
     VertexIndex,
+    InstanceIndex,
     FragCoord,
+    FragDepth,
     ClipDistance,
     CullDistance,
+    FrontFacing,
+    HelperInvocation,
+    ViewportIndex,
     Array {
         element_kind: Box<VariableDecl>,
         array_stride: u32,
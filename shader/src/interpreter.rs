@@ -1,8 +1,11 @@
 use crate::glsl::{FragmentShaderOutput, VertexShaderOutput};
 use crate::il;
-use common::consts::{MAX_CLIP_DISTANCES, MAX_CULL_DISTANCES};
+use common::consts::{
+    MAX_CLIP_DISTANCES, MAX_COLOR_ATTACHMENTS, MAX_CULL_DISTANCES, MAX_SAMPLE_COUNT,
+    MAX_VARYING_LOCATIONS,
+};
 use common::graphics::VertexInputState;
-use common::math::{Format, Fragment, Vector4, Vertex};
+use common::math::{Format, Fragment, FragmentQuad, Vector4, Vertex};
 use hashbrown::HashMap;
 use log::warn;
 
@@ -19,6 +22,32 @@ impl Interpreter {
 }
 
 impl Interpreter {
+    pub(crate) fn input_interpolations(
+        &self,
+    ) -> [il::Interpolation; MAX_VARYING_LOCATIONS as usize] {
+        self.il.input_interpolations()
+    }
+
+    pub(crate) fn input_centroids(&self) -> [bool; MAX_VARYING_LOCATIONS as usize] {
+        self.il.input_centroids()
+    }
+
+    pub(crate) fn input_locations(&self) -> [bool; MAX_VARYING_LOCATIONS as usize] {
+        self.il.input_locations()
+    }
+
+    pub(crate) const fn early_fragment_tests(&self) -> bool {
+        self.il.execution_modes.early_fragment_tests
+    }
+
+    pub(crate) fn writes_frag_depth(&self) -> bool {
+        self.il.writes_frag_depth()
+    }
+
+    pub(crate) fn can_discard(&self) -> bool {
+        self.il.can_discard()
+    }
+
     pub(crate) fn execute_vertex_shader(
         &self,
         _vertex_input_state: &VertexInputState,
@@ -45,25 +74,72 @@ impl Interpreter {
         outputs
     }
 
+    /// Executes fragment shader for each quad in lockstep, so that derivatives can be computed
+    /// as differences between invocations of quad.
+    /// Returns outputs of non-helper invocations which were not discarded.
     pub(crate) fn execute_fragment_shader(
         &self,
-        fragments: Vec<Fragment>,
+        quads: Vec<FragmentQuad>,
     ) -> Vec<FragmentShaderOutput> {
         let mut outputs: Vec<FragmentShaderOutput> = vec![];
+        let writes_frag_depth = self.writes_frag_depth();
+        let output_locations = self.il.output_locations();
+
+        for quad in quads {
+            let mut states = quad.map(|fragment| {
+                let mut state = State::new();
+                state.set_fragment_shader_input(fragment);
+                state
+            });
+
+            // Invocations stop executing after return or `OpKill`.
+            let mut active = [true; 4];
+            while let Some(lane) = active.iter().position(|&active| active) {
+                // NOTE: Without control flow all active invocations of quad share program counter.
+                debug_assert!(
+                    states
+                        .iter()
+                        .zip(&active)
+                        .all(|(state, &active)| !active || state.pc == states[lane].pc),
+                    "invocations of quad diverged"
+                );
+                let instruction = &self.il.instructions[states[lane].pc];
+                match instruction {
+                    il::Instruction::MathDPdx { id, p, coarse } => {
+                        State::il_derivative(&mut states, id, p, DerivativeKind::DPdx, *coarse);
+                    }
+                    il::Instruction::MathDPdy { id, p, coarse } => {
+                        State::il_derivative(&mut states, id, p, DerivativeKind::DPdy, *coarse);
+                    }
+                    il::Instruction::MathFwidth { id, p, coarse } => {
+                        State::il_derivative(&mut states, id, p, DerivativeKind::Fwidth, *coarse);
+                    }
+                    _ => {
+                        for (state, active) in states.iter_mut().zip(&mut active) {
+                            if *active && state.interpret_il_instruction(instruction) {
+                                *active = false;
+                            }
+                        }
+                    }
+                };
+            }
 
-        for fragment in fragments {
-            let mut state = State::new();
-            state.set_fragment_shader_input(fragment);
-
-            loop {
-                let instruction = &self.il.instructions[state.pc];
-                let end = state.interpret_il_instruction(instruction);
-                if end {
-                    break;
+            for (fragment, state) in quad.iter().zip(&mut states) {
+                if fragment.is_helper_invocation() || state.killed {
+                    continue;
                 }
+                let sample_depths = if writes_frag_depth {
+                    [state.frag_depth(); MAX_SAMPLE_COUNT as usize]
+                } else {
+                    fragment.sample_depths
+                };
+                outputs.push(FragmentShaderOutput {
+                    front_facing: fragment.front_facing,
+                    sample_mask: fragment.sample_mask,
+                    sample_depths,
+                    ..state.fragment_shader_output(&output_locations)
+                });
             }
-
-            outputs.push(state.fragment_shader_output());
         }
         outputs
     }
@@ -72,6 +148,8 @@ impl Interpreter {
 #[derive(Debug)]
 struct State {
     pc: usize,
+    /// Invocation executed `OpKill`.
+    killed: bool,
     labels: HashMap<u32, usize>,
     memory: Vec<u8>,
     memory_last_idx: u32,
@@ -81,7 +159,8 @@ struct State {
     pointer_variables: Vec<PointerVariable>,
 
     built_in_variables: HashMap<BuiltIn, Variable>,
-    location_variables: HashMap<u32, Variable>,
+    input_location_variables: HashMap<u32, Variable>,
+    output_location_variables: HashMap<u32, Variable>,
 
     il_variables: HashMap<il::Variable, Variable>,
 }
@@ -90,6 +169,7 @@ impl State {
     fn new() -> Self {
         Self {
             pc: 0,
+            killed: false,
             labels: Default::default(),
             memory: vec![0_u8; 10000], // TODO: Max memory size.
             memory_last_idx: 0,
@@ -97,7 +177,8 @@ impl State {
             struct_variables: vec![],
             pointer_variables: vec![],
             built_in_variables: Default::default(),
-            location_variables: Default::default(),
+            input_location_variables: Default::default(),
+            output_location_variables: Default::default(),
             il_variables: Default::default(),
         }
     }
@@ -160,6 +241,18 @@ impl State {
             bytemuck::cast_slice(&[vertex.index]),
         );
 
+        let memory_region = self.allocate_memory(std::mem::size_of::<u32>() as u32);
+        let variable = self.add_array_variable(ArrayVariable {
+            memory_region,
+            stride: std::mem::size_of::<u32>() as u32,
+        });
+        self.built_in_variables
+            .insert(BuiltIn::InstanceIndex, variable);
+        self.store_imm32(
+            self.array_variable(self.built_in_variable(BuiltIn::InstanceIndex)),
+            bytemuck::cast_slice(&[vertex.instance_index]),
+        );
+
         let memory_region =
             self.allocate_memory(std::mem::size_of::<f32>() as u32 * MAX_CLIP_DISTANCES);
         let variable = self.add_array_variable(ArrayVariable {
@@ -186,17 +279,40 @@ impl State {
             bytemuck::cast_slice(&[0.0f32, 0.0f32, 0.0f32, 0.0f32]),
         );
 
-        let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
+        let memory_region = self.allocate_memory(std::mem::size_of::<u32>() as u32);
         let variable = self.add_array_variable(ArrayVariable {
             memory_region,
-            stride: std::mem::size_of::<f32>() as u32,
+            stride: std::mem::size_of::<u32>() as u32,
         });
-        self.location_variables.insert(0, variable);
+        self.built_in_variables
+            .insert(BuiltIn::ViewportIndex, variable);
         self.store_imm32(
-            self.array_variable(self.location_variable(0)),
-            bytemuck::cast_slice(vertex.position.get_as_f32_array().as_slice()),
+            self.array_variable(self.built_in_variable(BuiltIn::ViewportIndex)),
+            bytemuck::cast_slice(&[0u32]),
         );
-        warn!("TODO: use vertex bindings and vertex attributes?");
+
+        for (location, attribute) in vertex.attributes.iter().enumerate() {
+            let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
+            let variable = self.add_array_variable(ArrayVariable {
+                memory_region,
+                stride: std::mem::size_of::<f32>() as u32,
+            });
+            self.input_location_variables
+                .insert(location as u32, variable);
+            self.store_imm32(
+                self.array_variable(variable),
+                bytemuck::cast_slice(attribute.get_as_f32_array().as_slice()),
+            );
+        }
+
+        for location in 0..MAX_VARYING_LOCATIONS {
+            let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
+            let variable = self.add_array_variable(ArrayVariable {
+                memory_region,
+                stride: std::mem::size_of::<f32>() as u32,
+            });
+            self.output_location_variables.insert(location, variable);
+        }
     }
 
     fn vertex_shader_output(&self) -> VertexShaderOutput {
@@ -216,11 +332,25 @@ impl State {
             *bytemuck::from_bytes::<[f32; MAX_CLIP_DISTANCES as usize]>(bytemuck::cast_slice(
                 self.load_imm32(self.array_variable(self.built_in_variable(BuiltIn::ClipDistance))),
             ));
+        let viewport_index = *bytemuck::from_bytes::<u32>(bytemuck::cast_slice(
+            self.load_imm32(self.array_variable(self.built_in_variable(BuiltIn::ViewportIndex))),
+        ));
+        let mut varyings = [Vector4::default(); MAX_VARYING_LOCATIONS as usize];
+        for (location, varying) in varyings.iter_mut().enumerate() {
+            *varying = Vector4::from_vertex_buffer_bytes(
+                Format::R32G32B32A32Sfloat,
+                bytemuck::cast_slice(self.load_imm32(
+                    self.array_variable(self.output_location_variable(location as u32)),
+                )),
+            );
+        }
         VertexShaderOutput {
             position,
             point_size,
             vertex_index,
             clip_distances,
+            viewport_index,
+            varyings,
         }
     }
 
@@ -236,32 +366,96 @@ impl State {
             bytemuck::cast_slice(fragment.position.get_as_f32_array().as_slice()),
         );
 
-        let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
+        let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32);
         let variable = self.add_array_variable(ArrayVariable {
             memory_region,
             stride: std::mem::size_of::<f32>() as u32,
         });
-        self.location_variables.insert(0, variable);
+        self.built_in_variables.insert(BuiltIn::FragDepth, variable);
         self.store_imm32(
-            self.array_variable(self.location_variable(0)),
-            bytemuck::cast_slice(fragment.color.get_as_f32_array().as_slice()),
+            self.array_variable(self.built_in_variable(BuiltIn::FragDepth)),
+            &[fragment.position.get_as_sfloat32(2).to_bits()],
         );
+
+        let memory_region = self.allocate_memory(std::mem::size_of::<bool>() as u32);
+        let variable = self.add_array_variable(ArrayVariable {
+            memory_region,
+            stride: std::mem::size_of::<bool>() as u32,
+        });
+        self.built_in_variables
+            .insert(BuiltIn::FrontFacing, variable);
+        self.memory_mut(&memory_region)
+            .copy_from_slice(&[fragment.front_facing as u8]);
+
+        let memory_region = self.allocate_memory(std::mem::size_of::<bool>() as u32);
+        let variable = self.add_array_variable(ArrayVariable {
+            memory_region,
+            stride: std::mem::size_of::<bool>() as u32,
+        });
+        self.built_in_variables
+            .insert(BuiltIn::HelperInvocation, variable);
+        self.memory_mut(&memory_region)
+            .copy_from_slice(&[fragment.is_helper_invocation() as u8]);
+
+        for (location, varying) in fragment.varyings.iter().enumerate() {
+            let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
+            let variable = self.add_array_variable(ArrayVariable {
+                memory_region,
+                stride: std::mem::size_of::<f32>() as u32,
+            });
+            self.input_location_variables
+                .insert(location as u32, variable);
+            self.store_imm32(
+                self.array_variable(variable),
+                bytemuck::cast_slice(varying.get_as_f32_array().as_slice()),
+            );
+        }
+
+        for location in 0..MAX_COLOR_ATTACHMENTS {
+            let memory_region = self.allocate_memory(std::mem::size_of::<f32>() as u32 * 4);
+            let variable = self.add_array_variable(ArrayVariable {
+                memory_region,
+                stride: std::mem::size_of::<f32>() as u32,
+            });
+            self.output_location_variables.insert(location, variable);
+        }
         warn!("TODO: use descriptors");
     }
 
-    fn fragment_shader_output(&mut self) -> FragmentShaderOutput {
+    fn frag_depth(&self) -> f32 {
+        *bytemuck::from_bytes::<f32>(bytemuck::cast_slice(
+            self.load_imm32(self.array_variable(self.built_in_variable(BuiltIn::FragDepth))),
+        ))
+    }
+
+    fn fragment_shader_output(
+        &mut self,
+        output_locations: &[bool; MAX_VARYING_LOCATIONS as usize],
+    ) -> FragmentShaderOutput {
         let position = Vector4::from_vertex_buffer_bytes(
             Format::R32G32B32A32Sfloat,
             bytemuck::cast_slice(
                 self.load_imm32(self.array_variable(self.built_in_variable(BuiltIn::FragCoord))),
             ),
         );
-        warn!("TODO: Determine color using fragment shader interface");
-        let color = Vector4::from_vertex_buffer_bytes(
-            Format::R32G32B32A32Sfloat,
-            bytemuck::cast_slice(self.load_imm32(self.array_variable(self.location_variable(0)))),
-        );
-        FragmentShaderOutput { position, color }
+        let mut colors = [None; MAX_COLOR_ATTACHMENTS as usize];
+        for (location, color) in colors.iter_mut().enumerate() {
+            if !output_locations[location] {
+                continue;
+            }
+            *color = Some(Vector4::from_vertex_buffer_bytes(
+                Format::R32G32B32A32Sfloat,
+                bytemuck::cast_slice(self.load_imm32(
+                    self.array_variable(self.output_location_variable(location as u32)),
+                )),
+            ));
+        }
+        FragmentShaderOutput {
+            position,
+            colors,
+            front_facing: true,
+            ..Default::default()
+        }
     }
 }
 
@@ -269,10 +463,15 @@ impl State {
 enum BuiltIn {
     Position,
     FragCoord,
+    FragDepth,
     PointSize,
     VertexIndex,
+    InstanceIndex,
     ClipDistance,
     CullDistance,
+    FrontFacing,
+    HelperInvocation,
+    ViewportIndex,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -355,13 +554,23 @@ impl Variable {
                     stride: Self::size(decl),
                 })
             }
-            il::VariableBacking::Location { number } => state.location_variable(*number),
+            il::VariableBacking::InputLocation { number } => state.input_location_variable(*number),
+            il::VariableBacking::OutputLocation { number } => {
+                state.output_location_variable(*number)
+            }
             il::VariableBacking::Position => state.built_in_variable(BuiltIn::Position),
             il::VariableBacking::PointSize => state.built_in_variable(BuiltIn::PointSize),
             il::VariableBacking::VertexIndex => state.built_in_variable(BuiltIn::VertexIndex),
+            il::VariableBacking::InstanceIndex => state.built_in_variable(BuiltIn::InstanceIndex),
             il::VariableBacking::FragCoord => state.built_in_variable(BuiltIn::FragCoord),
+            il::VariableBacking::FragDepth => state.built_in_variable(BuiltIn::FragDepth),
             il::VariableBacking::ClipDistance => state.built_in_variable(BuiltIn::ClipDistance),
             il::VariableBacking::CullDistance => state.built_in_variable(BuiltIn::CullDistance),
+            il::VariableBacking::FrontFacing => state.built_in_variable(BuiltIn::FrontFacing),
+            il::VariableBacking::HelperInvocation => {
+                state.built_in_variable(BuiltIn::HelperInvocation)
+            }
+            il::VariableBacking::ViewportIndex => state.built_in_variable(BuiltIn::ViewportIndex),
             il::VariableBacking::Array {
                 element_kind,
                 array_stride,
@@ -447,18 +656,27 @@ impl State {
             .unwrap_or_else(|| unreachable!())
     }
 
-    pub(crate) fn location_variable(&self, number: u32) -> Variable {
+    pub(crate) fn input_location_variable(&self, number: u32) -> Variable {
+        *self
+            .input_location_variables
+            .get(&number)
+            .unwrap_or_else(|| unreachable!())
+    }
+
+    pub(crate) fn output_location_variable(&self, number: u32) -> Variable {
         *self
-            .location_variables
+            .output_location_variables
             .get(&number)
             .unwrap_or_else(|| unreachable!())
     }
 
+    /// Allocates region aligned to 4 bytes, so that it can be accessed as 32-bit words even
+    /// after allocations of `bool`s.
     fn allocate_memory(&mut self, size: u32) -> MemoryRegion {
         MemoryRegion {
             address: {
                 let address = self.memory_last_idx;
-                self.memory_last_idx += size;
+                self.memory_last_idx += size.next_multiple_of(std::mem::size_of::<u32>() as u32);
                 address
             },
             size,
@@ -638,8 +856,17 @@ impl State {
         let obj1 = self.array_variable(self.il_variable(obj1));
         let obj2 = self.array_variable(self.il_variable(obj2));
         assert!(cond.is_bool());
-        let cond = self.memory(&cond.memory_region) != &[0];
-        self.store_array(result, if cond { obj1 } else { obj2 });
+        if cond.len() == 1 {
+            let cond = self.memory(&cond.memory_region) != &[0];
+            self.store_array(result, if cond { obj1 } else { obj2 });
+            return;
+        }
+        // Vector condition selects each component separately.
+        for i in 0..cond.len() {
+            let component = self.memory(&cond.indexed(i).memory_region) != [0];
+            let obj = if component { obj1 } else { obj2 };
+            self.store_array(result.indexed(i), obj.indexed(i));
+        }
     }
 }
 
@@ -660,6 +887,13 @@ enum BinaryOpKind {
     LessThanU32U32,
 }
 
+#[derive(Debug, Copy, Clone)]
+enum DerivativeKind {
+    DPdx,
+    DPdy,
+    Fwidth,
+}
+
 #[derive(Debug, Copy, Clone)]
 enum ConvertKind {
     I32F32,
@@ -802,6 +1036,48 @@ impl State {
 }
 
 impl State {
+    /// Computes derivative of `p` for each invocation of quad as difference between
+    /// horizontally or vertically adjacent invocations, see [`FragmentQuad`].
+    /// Fine derivatives use row or column of invocation, coarse ones use first row or column.
+    // NOTE: https://registry.khronos.org/vulkan/specs/1.3-extensions/html/vkspec.html#shaders-derivative-operations
+    fn il_derivative(
+        states: &mut [Self; 4],
+        result: &il::Variable,
+        p: &il::Variable,
+        kind: DerivativeKind,
+        coarse: bool,
+    ) {
+        let values = states.each_ref().map(|state| {
+            let p = state.array_variable(state.il_variable(p));
+            state
+                .memory(&p.memory_region)
+                .chunks_exact(std::mem::size_of::<f32>())
+                .map(|bytes| {
+                    f32::from_ne_bytes(bytes.try_into().unwrap_or_else(|_| unreachable!()))
+                })
+                .collect::<Vec<_>>()
+        });
+        let difference =
+            |a: usize, b: usize, component: usize| values[b][component] - values[a][component];
+        for (lane, state) in states.iter_mut().enumerate() {
+            let (row, column) = if coarse { (0, 0) } else { (lane / 2, lane % 2) };
+            let result = state.array_variable(state.il_variable(result));
+            for component in 0..values[lane].len() {
+                let dx = difference(2 * row, 2 * row + 1, component);
+                let dy = difference(column, column + 2, component);
+                let value = match kind {
+                    DerivativeKind::DPdx => dx,
+                    DerivativeKind::DPdy => dy,
+                    DerivativeKind::Fwidth => dx.abs() + dy.abs(),
+                };
+                state.memory_mut(&result.memory_region)[component * std::mem::size_of::<f32>()
+                    ..(component + 1) * std::mem::size_of::<f32>()]
+                    .copy_from_slice(bytemuck::bytes_of(&value));
+            }
+            state.pc += 1;
+        }
+    }
+
     fn interpret_il_instruction(&mut self, instruction: &il::Instruction) -> bool {
         match instruction {
             il::Instruction::Label { id } => {
@@ -879,6 +1155,12 @@ impl State {
             il::Instruction::MathConvertU32F32 { id, op } => {
                 self.il_convert(id, op, ConvertKind::U32F32);
             }
+            il::Instruction::MathDPdx { .. }
+            | il::Instruction::MathDPdy { .. }
+            | il::Instruction::MathFwidth { .. } => {
+                // Derivatives are computed across quad, see `il_derivative`.
+                unreachable!()
+            }
             il::Instruction::Return => {
                 return true;
             }
@@ -903,7 +1185,8 @@ impl State {
                 todo!()
             }
             il::Instruction::Kill => {
-                todo!()
+                self.killed = true;
+                return true;
             }
         };
         self.pc += 1;
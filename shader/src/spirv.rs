@@ -10,6 +10,7 @@ use rspirv::spirv as spirv_;
 #[derive(Debug, Clone)]
 pub struct Spirv {
     pub(crate) entry_point: EntryPoint,
+    pub(crate) execution_modes: ExecutionModes,
     pub(crate) objects: HashMap<ObjectId, Object>,
     pub(crate) functions: HashMap<ObjectId, Function>,
 }
@@ -28,6 +29,7 @@ impl Spirv {
         println!("spirv shader:\n{}", module.disassemble());
 
         let entry_point = EntryPoint::parse(&module)?;
+        let execution_modes = ExecutionModes::parse(&module);
         Version::parse(&module)?;
         Capability::parse(&module)?;
         MemoryModel::parse(&module)?;
@@ -37,6 +39,7 @@ impl Spirv {
 
         Ok(Self {
             entry_point,
+            execution_modes,
             objects,
             functions,
         })
@@ -77,6 +80,29 @@ impl EntryPoint {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ExecutionModes {
+    pub(crate) early_fragment_tests: bool,
+}
+
+impl ExecutionModes {
+    /// Parses OpExecutionMode, modes not affecting pipeline are ignored.
+    fn parse(module: &Module_) -> Self {
+        Self {
+            early_fragment_tests: module.execution_modes.iter().any(|execution_mode| {
+                matches!(
+                    execution_mode.operands[..],
+                    [
+                        _,
+                        Operand_::ExecutionMode(spirv_::ExecutionMode::EarlyFragmentTests),
+                        ..
+                    ]
+                )
+            }),
+        }
+    }
+}
+
 #[allow(dead_code)]
 #[derive(Debug, Clone, Default)]
 struct Version {
@@ -569,7 +595,6 @@ pub enum Pointer {
 #[derive(Debug, Clone)]
 pub struct MemoryObject {
     pub(crate) type_: ObjectId,
-    #[allow(dead_code)]
     pub(crate) storage_class: StorageClass,
     pub(crate) decorations: Decorations,
 }
@@ -584,6 +609,9 @@ pub struct Decorations {
     pub(crate) array_stride: Option<u32>, // TODO: Implement Offset decoration for array type.
     pub(crate) descriptor_set: Option<u32>, // TODO: Implement DescriptorSet decoration for variable type.
     pub(crate) binding_point: Option<u32>,  // TODO: Implement Binding decoration for variable type.
+    pub(crate) flat: bool,
+    pub(crate) no_perspective: bool,
+    pub(crate) centroid: bool,
 }
 
 impl Decorations {
@@ -612,6 +640,9 @@ impl Decorations {
             (spirv_::Decoration::Binding, &[Operand_::LiteralInt32(binding_point)]) => {
                 self.binding_point = Some(binding_point)
             }
+            (spirv_::Decoration::Flat, &[]) => self.flat = true,
+            (spirv_::Decoration::NoPerspective, &[]) => self.no_perspective = true,
+            (spirv_::Decoration::Centroid, &[]) => self.centroid = true,
             _ => unimplemented!("{:?}, {:?}", value, literals),
         }
     }
@@ -622,9 +653,14 @@ pub enum BuiltInDecoration {
     Position,
     PointSize,
     VertexIndex,
+    InstanceIndex,
     FragCoord,
+    FragDepth,
     ClipDistance,
     CullDistance,
+    FrontFacing,
+    HelperInvocation,
+    ViewportIndex,
 }
 
 impl BuiltInDecoration {
@@ -633,9 +669,14 @@ impl BuiltInDecoration {
             Operand_::BuiltIn(spirv_::BuiltIn::Position) => Self::Position,
             Operand_::BuiltIn(spirv_::BuiltIn::PointSize) => Self::PointSize,
             Operand_::BuiltIn(spirv_::BuiltIn::VertexIndex) => Self::VertexIndex,
+            Operand_::BuiltIn(spirv_::BuiltIn::InstanceIndex) => Self::InstanceIndex,
             Operand_::BuiltIn(spirv_::BuiltIn::FragCoord) => Self::FragCoord,
+            Operand_::BuiltIn(spirv_::BuiltIn::FragDepth) => Self::FragDepth,
             Operand_::BuiltIn(spirv_::BuiltIn::ClipDistance) => Self::ClipDistance,
             Operand_::BuiltIn(spirv_::BuiltIn::CullDistance) => Self::CullDistance,
+            Operand_::BuiltIn(spirv_::BuiltIn::FrontFacing) => Self::FrontFacing,
+            Operand_::BuiltIn(spirv_::BuiltIn::HelperInvocation) => Self::HelperInvocation,
+            Operand_::BuiltIn(spirv_::BuiltIn::ViewportIndex) => Self::ViewportIndex,
             _ => unimplemented!("{operand:?}"),
         }
     }
@@ -900,6 +941,27 @@ pub enum Instruction {
         result_type: ObjectId,
         operand: ObjectId,
     },
+    /// `OpDPdx`, `OpDPdxFine` or `OpDPdxCoarse`.
+    DPdx {
+        result_id: ObjectId,
+        result_type: ObjectId,
+        p: ObjectId,
+        coarse: bool,
+    },
+    /// `OpDPdy`, `OpDPdyFine` or `OpDPdyCoarse`.
+    DPdy {
+        result_id: ObjectId,
+        result_type: ObjectId,
+        p: ObjectId,
+        coarse: bool,
+    },
+    /// `OpFwidth`, `OpFwidthFine` or `OpFwidthCoarse`.
+    Fwidth {
+        result_id: ObjectId,
+        result_type: ObjectId,
+        p: ObjectId,
+        coarse: bool,
+    },
     CompositeExtract {
         result_id: ObjectId,
         result_type: ObjectId,
@@ -1162,6 +1224,40 @@ impl Instruction {
                 result_type: ObjectId(result_type),
                 operand: ObjectId(*operand),
             }),
+            // NOTE: Unqualified derivatives are computed as fine derivatives.
+            (
+                op @ (spirv_::Op::DPdx | spirv_::Op::DPdxFine | spirv_::Op::DPdxCoarse),
+                &Some(result_type),
+                &Some(result_id),
+                [Operand_::IdRef(p)],
+            ) => Ok(Self::DPdx {
+                result_id: ObjectId(result_id),
+                result_type: ObjectId(result_type),
+                p: ObjectId(*p),
+                coarse: *op == spirv_::Op::DPdxCoarse,
+            }),
+            (
+                op @ (spirv_::Op::DPdy | spirv_::Op::DPdyFine | spirv_::Op::DPdyCoarse),
+                &Some(result_type),
+                &Some(result_id),
+                [Operand_::IdRef(p)],
+            ) => Ok(Self::DPdy {
+                result_id: ObjectId(result_id),
+                result_type: ObjectId(result_type),
+                p: ObjectId(*p),
+                coarse: *op == spirv_::Op::DPdyCoarse,
+            }),
+            (
+                op @ (spirv_::Op::Fwidth | spirv_::Op::FwidthFine | spirv_::Op::FwidthCoarse),
+                &Some(result_type),
+                &Some(result_id),
+                [Operand_::IdRef(p)],
+            ) => Ok(Self::Fwidth {
+                result_id: ObjectId(result_id),
+                result_type: ObjectId(result_type),
+                p: ObjectId(*p),
+                coarse: *op == spirv_::Op::FwidthCoarse,
+            }),
             (
                 spirv_::Op::CompositeExtract,
                 &Some(result_type),